@@ -0,0 +1,209 @@
+use std::time::{Duration, SystemTime};
+
+use chrono::{Local, NaiveDate, NaiveTime, TimeZone};
+use serde::Serialize;
+use typeshare::typeshare;
+
+use crate::proto::{DeviceStatus, OperatingMode, ParsedDeviceStatus, ShutDownCode};
+
+/// The stretch of time that counts as one night, in local time
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Night {
+    pub start: SystemTime,
+    pub end: SystemTime,
+}
+
+impl Night {
+    /// The night starts in the evening of the date, and ends at noon the next day
+    pub const START_HOUR: u32 = 18;
+    pub const END_HOUR: u32 = 12;
+
+    pub fn new(date: NaiveDate) -> Option<Night> {
+        let at = |date: NaiveDate, hour| {
+            let time = NaiveTime::from_hms_opt(hour, 0, 0)?;
+            Local
+                .from_local_datetime(&date.and_time(time))
+                .earliest()
+                .map(SystemTime::from)
+        };
+        Some(Night {
+            start: at(date, Self::START_HOUR)?,
+            end: at(date.succ_opt()?, Self::END_HOUR)?,
+        })
+    }
+}
+
+#[typeshare]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TempStats {
+    /// As degrees C
+    pub min: f32,
+    /// As degrees C
+    pub max: f32,
+    /// As degrees C, weighted by how long each temperature lasted
+    pub mean: f32,
+}
+
+#[typeshare]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct TargetDeviation {
+    /// How far the actual temperature was from the target on average, in degrees C
+    pub mean: f32,
+    /// The furthest the actual temperature got from the target, in degrees C
+    pub max: f32,
+}
+
+#[typeshare]
+#[serde_with::serde_as]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ModeDuration {
+    pub mode: OperatingMode,
+    #[typeshare(serialized_as = u64)]
+    #[serde_as(as = "serde_with::DurationSeconds<u64>")]
+    pub duration: Duration,
+}
+
+#[typeshare]
+#[serde_with::serde_as]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct ShutdownEvent {
+    /// As seconds since the unix epoch
+    #[typeshare(serialized_as = u64)]
+    #[serde_as(as = "serde_with::TimestampSeconds<i64>")]
+    pub time: SystemTime,
+    pub code: ShutDownCode,
+}
+
+/// How the device behaved over one night
+#[typeshare]
+#[serde_with::serde_as]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct NightSummary {
+    /// As seconds since the unix epoch
+    #[typeshare(serialized_as = u64)]
+    #[serde_as(as = "serde_with::TimestampSeconds<i64>")]
+    pub start: SystemTime,
+    /// As seconds since the unix epoch
+    #[typeshare(serialized_as = u64)]
+    #[serde_as(as = "serde_with::TimestampSeconds<i64>")]
+    pub end: SystemTime,
+    /// Each mode the device was in, and for how long. Time the device wasn't heard from isn't counted
+    pub modes: Vec<ModeDuration>,
+    /// As a percent 0 - 100, while the device was running
+    pub average_fan: Option<f32>,
+    pub actual_temp: Option<TempStats>,
+    pub ambient_temp: Option<TempStats>,
+    /// Only counts the time the device was running
+    pub target_deviation: Option<TargetDeviation>,
+    pub shutdowns: Vec<ShutdownEvent>,
+}
+
+/// Sums up values that each held for a while
+#[derive(Default)]
+struct Weighted {
+    total: f64,
+    seconds: f64,
+    min: Option<f32>,
+    max: Option<f32>,
+}
+
+impl Weighted {
+    fn add(&mut self, value: f32, duration: Duration) {
+        if duration.is_zero() {
+            return;
+        }
+        self.total += value as f64 * duration.as_secs_f64();
+        self.seconds += duration.as_secs_f64();
+        self.min = Some(self.min.map_or(value, |i| i.min(value)));
+        self.max = Some(self.max.map_or(value, |i| i.max(value)));
+    }
+
+    fn mean(&self) -> Option<f32> {
+        (self.seconds > 0.0).then(|| (self.total / self.seconds) as f32)
+    }
+
+    fn stats(&self) -> Option<TempStats> {
+        Some(TempStats {
+            min: self.min?,
+            max: self.max?,
+            mean: self.mean()?,
+        })
+    }
+}
+
+impl NightSummary {
+    /// A sample is only trusted for this long. Unchanged statuses are still recorded every so often,
+    /// so a longer gap means the device wasn't connected
+    pub const MAX_SAMPLE_AGE: Duration = Duration::from_secs(5 * 60);
+
+    /// Works out the summary from the recorded samples, which have to be oldest first.
+    /// Samples from before the night count for as long as they were still in effect once it started.
+    pub fn new(night: Night, samples: &[(SystemTime, DeviceStatus)]) -> NightSummary {
+        let mut modes: Vec<ModeDuration> = Vec::new();
+        let mut fan = Weighted::default();
+        let mut actual = Weighted::default();
+        let mut ambient = Weighted::default();
+        let mut deviation = Weighted::default();
+        let mut shutdowns = Vec::new();
+
+        let mut previous_code = ShutDownCode::Normal;
+        for (index, (time, status)) in samples.iter().enumerate() {
+            let status = ParsedDeviceStatus::from(*status);
+            if *time >= night.start
+                && *time < night.end
+                && status.shutdown_code != ShutDownCode::Normal
+                && status.shutdown_code != previous_code
+            {
+                shutdowns.push(ShutdownEvent {
+                    time: *time,
+                    code: status.shutdown_code,
+                });
+            }
+            previous_code = status.shutdown_code;
+
+            // The sample holds until the next one, or until it's too old to trust
+            let next = samples.get(index + 1).map_or(night.end, |i| i.0);
+            let from = (*time).max(night.start);
+            let until = next.min(night.end).min(*time + Self::MAX_SAMPLE_AGE);
+            let Ok(duration) = until.duration_since(from) else {
+                continue;
+            };
+            if duration.is_zero() {
+                continue;
+            }
+
+            match modes.iter_mut().find(|i| i.mode == status.operating_mode) {
+                Some(mode) => mode.duration += duration,
+                None => modes.push(ModeDuration {
+                    mode: status.operating_mode,
+                    duration,
+                }),
+            }
+
+            actual.add(status.actual_temp, duration);
+            ambient.add(status.ambient_temp, duration);
+            if !matches!(
+                status.operating_mode,
+                OperatingMode::Standby | OperatingMode::Wait
+            ) {
+                // Already a percent once parsed
+                fan.add(status.fan_step as f32, duration);
+                deviation.add((status.actual_temp - status.target_temp).abs(), duration);
+            }
+        }
+
+        NightSummary {
+            start: night.start,
+            end: night.end,
+            modes,
+            average_fan: fan.mean(),
+            actual_temp: actual.stats(),
+            ambient_temp: ambient.stats(),
+            target_deviation: deviation.stats().map(|i| TargetDeviation {
+                mean: i.mean,
+                max: i.max,
+            }),
+            shutdowns,
+        }
+    }
+}
@@ -1,40 +1,179 @@
-use std::sync::Arc;
+use std::{
+    fs::File,
+    io::BufWriter,
+    path::PathBuf,
+    sync::Arc,
+    time::{Duration, SystemTime},
+};
 
-use crate::proto::{Command, ParsedDeviceStatus};
-use crate::state::{AppState, PeripheralResult, UserPreferences};
+use crate::analytics::{Night, NightSummary};
+use crate::export::{write_history, ExportError, ExportFormat, TimeRange};
+use crate::proto::{
+    BiorhythmProgram, Command, DeviceSettings, ExpectedStatus, InterfaceError, MemoryPreset,
+    ParsedDeviceStatus, TempParam,
+};
+use crate::scheduler::{RuleSpec, ScheduleEntry, ScheduleError, ScheduleRule};
+use crate::state::{
+    self, AppState, ConfirmationPolicy, DBError, DeviceError, DeviceFault, DeviceGroup, DeviceInfo,
+    FirmwareUpdateOutcome, KnownDevice, PeripheralResult, StatusSample, UserPreferences,
+};
 use btleplug::api::{Central, Peripheral as _};
+use chrono::NaiveDate;
 use serde::Serialize;
-use tauri::State;
+use tauri::{AppHandle, State};
+use thiserror::Error;
 use tokio::sync::RwLock;
 use typeshare::typeshare;
 
 type AppStateHandle<'a> = State<'a, Arc<RwLock<AppState>>>;
 
+/// The error every command returns to the UI.
+/// Each variant carries a message that can be shown to the user as is.
+#[typeshare]
+#[derive(Error, Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", content = "value")]
+pub enum CommandError {
+    /// The device or adapter isn't around, or was never connected to
+    #[error("{0}")]
+    NotFound(String),
+    /// There's no Bluetooth adapter to talk to devices with
+    #[error("{0}")]
+    NoAdapter(String),
+    #[error("{0}")]
+    MissingCharacteristic(String),
+    #[error("{0}")]
+    Bluetooth(String),
+    /// The arguments were out of range, or the device sent back something that couldn't be understood
+    #[error("{0}")]
+    InvalidParameter(String),
+    #[error("{0}")]
+    Timeout(String),
+    /// The command needs a valid confirmation before it's sent
+    #[error("{0}")]
+    Unconfirmed(String),
+    /// The device ran the request, but reported that it failed
+    #[error("{0}")]
+    DeviceFailed(String),
+    /// Saving to the app's database failed
+    #[error("{0}")]
+    Storage(String),
+    /// Reading or writing a file the user picked failed
+    #[error("{0}")]
+    File(String),
+    #[error("{0}")]
+    Internal(String),
+}
+
+impl From<DeviceError> for CommandError {
+    fn from(value: DeviceError) -> Self {
+        let message = value.to_string();
+        match value {
+            DeviceError::InterfaceError(err) => err.into(),
+            DeviceError::BluetoothError(err) => err.into(),
+            DeviceError::DeviceNotFound
+            | DeviceError::AdapterNotFound
+            | DeviceError::GroupNotFound => Self::NotFound(message),
+            DeviceError::NoAdapter => Self::NoAdapter(message),
+            DeviceError::MissingCharacteristic => Self::MissingCharacteristic(message),
+            DeviceError::Timeout => Self::Timeout(message),
+            DeviceError::ConfirmationRequired | DeviceError::InvalidConfirmation => {
+                Self::Unconfirmed(message)
+            }
+            DeviceError::NotAcknowledged { .. }
+            | DeviceError::WifiTestFailed(_)
+            | DeviceError::FirmwareUpdateFailed(_) => Self::DeviceFailed(message),
+            DeviceError::ReconnectFailed => Self::Bluetooth(message),
+            DeviceError::DBError(err) => err.into(),
+            DeviceError::InvalidBands(_) => Self::InvalidParameter(message),
+            DeviceError::TaskError(_) | DeviceError::RecvError(_) => Self::Internal(message),
+        }
+    }
+}
+
+impl From<DBError> for CommandError {
+    fn from(value: DBError) -> Self {
+        Self::Storage(value.to_string())
+    }
+}
+
+impl From<ExportError> for CommandError {
+    fn from(value: ExportError) -> Self {
+        let message = value.to_string();
+        match value {
+            ExportError::Io(_) => Self::File(message),
+            ExportError::Json(_) => Self::Internal(message),
+            ExportError::DBError(err) => err.into(),
+        }
+    }
+}
+
+impl From<ScheduleError> for CommandError {
+    fn from(value: ScheduleError) -> Self {
+        let message = value.to_string();
+        match value {
+            ScheduleError::RuleNotFound => Self::NotFound(message),
+            _ => Self::InvalidParameter(message),
+        }
+    }
+}
+
+impl From<InterfaceError> for CommandError {
+    fn from(value: InterfaceError) -> Self {
+        Self::InvalidParameter(value.to_string())
+    }
+}
+
+impl From<btleplug::Error> for CommandError {
+    fn from(value: btleplug::Error) -> Self {
+        let message = value.to_string();
+        match value {
+            btleplug::Error::DeviceNotFound => Self::NotFound(message),
+            btleplug::Error::TimedOut(_) => Self::Timeout(message),
+            _ => Self::Bluetooth(message),
+        }
+    }
+}
+
 #[typeshare]
 #[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct AdapterResult {
-    selected: String,
+    /// Missing when there's no adapter to use
+    selected: Option<String>,
     adapters: Vec<String>,
 }
 
 #[tauri::command]
-pub async fn get_btle_adapters(state: AppStateHandle<'_>) -> Result<AdapterResult, ()> {
+pub async fn get_btle_adapters(state: AppStateHandle<'_>) -> Result<AdapterResult, CommandError> {
     let state = state.read().await;
-    let selected = state.selected_adapter.adapter_info().await.unwrap();
+    let selected = match state.adapter() {
+        Ok(adapter) => Some(adapter.adapter_info().await?),
+        Err(_) => None,
+    };
     let mut adapters = Vec::new();
     for adapter in &state.all_adapters {
-        let name = adapter.adapter_info().await.unwrap().clone();
+        let name = adapter.adapter_info().await?;
         adapters.push(name);
     }
     Ok(AdapterResult { selected, adapters })
 }
 
 #[tauri::command]
-pub async fn scan_devices(state: AppStateHandle<'_>) -> Result<Vec<PeripheralResult>, ()> {
+pub async fn select_adapter(
+    state: AppStateHandle<'_>,
+    adapter: String,
+) -> Result<(), CommandError> {
+    state::switch_adapter(&state, &adapter).await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn scan_devices(
+    state: AppStateHandle<'_>,
+) -> Result<Vec<PeripheralResult>, CommandError> {
     let state = state.read().await;
 
-    state.scan_devices().await.unwrap();
-    let peripherals = state.get_peripherals().await.unwrap();
+    state.scan_devices().await?;
+    let peripherals = state.get_peripherals().await?;
 
     let mut result: Vec<PeripheralResult> = Vec::new();
 
@@ -54,61 +193,590 @@ pub async fn scan_devices(state: AppStateHandle<'_>) -> Result<Vec<PeripheralRes
 }
 
 #[tauri::command]
-pub async fn connect_device(state: AppStateHandle<'_>, id: String) -> Result<(), ()> {
-    state.write().await.connect_peripheral(&id).await.unwrap();
+pub async fn connect_device(state: AppStateHandle<'_>, id: String) -> Result<(), CommandError> {
+    state::connect_peripheral(&state, &id).await?;
     Ok(())
 }
 
 #[tauri::command]
-pub async fn disconnect_device(state: AppStateHandle<'_>, id: String) -> Result<(), ()> {
+pub async fn disconnect_device(state: AppStateHandle<'_>, id: String) -> Result<(), CommandError> {
     state.write().await.disconnect_peripheral(&id).await;
     Ok(())
 }
 
 #[tauri::command]
-pub async fn get_config(state: AppStateHandle<'_>) -> Result<UserPreferences, ()> {
+pub async fn get_known_devices(
+    state: AppStateHandle<'_>,
+) -> Result<Vec<KnownDevice>, CommandError> {
+    Ok(state.read().await.db.get_known_devices())
+}
+
+/// The logged faults, most recent first. Only the device's faults if an id is given
+#[tauri::command]
+pub async fn get_faults(
+    state: AppStateHandle<'_>,
+    id: Option<String>,
+) -> Result<Vec<DeviceFault>, CommandError> {
+    Ok(state.read().await.db.get_faults(id.as_deref())?)
+}
+
+#[tauri::command]
+pub async fn get_auto_reconnect(
+    state: AppStateHandle<'_>,
+    id: String,
+) -> Result<bool, CommandError> {
+    Ok(state.read().await.db.get_auto_reconnect(&id))
+}
+
+#[tauri::command]
+pub async fn set_auto_reconnect(
+    state: AppStateHandle<'_>,
+    id: String,
+    enabled: bool,
+) -> Result<(), CommandError> {
+    state.write().await.set_auto_reconnect(&id, enabled)?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_config(state: AppStateHandle<'_>) -> Result<UserPreferences, CommandError> {
     let state = state.read().await.db.get_config().unwrap_or_default();
     Ok(state)
 }
 
 #[tauri::command]
-pub async fn set_config(state: AppStateHandle<'_>, config: UserPreferences) -> Result<(), ()> {
-    state.read().await.db.set_config(&config);
+pub async fn set_config(
+    state: AppStateHandle<'_>,
+    config: UserPreferences,
+) -> Result<(), CommandError> {
+    let state = state.read().await;
+    state
+        .validate_bands(config.temp_bands())
+        .map_err(DeviceError::from)?;
+    state.db.set_config(&config)?;
     Ok(())
 }
 
+/// Sends any command to the device, apart from destructive ones which have their own confirmed commands
 #[tauri::command]
 pub async fn send_command(
     state: AppStateHandle<'_>,
     id: String,
     command: Command,
-) -> Result<(), ()> {
+) -> Result<(), CommandError> {
+    if command.is_destructive() {
+        return Err(DeviceError::ConfirmationRequired.into());
+    }
+
     state
         .read()
         .await
         .find_device_by_id(&id)
-        .ok_or(())?
+        .ok_or(DeviceError::DeviceNotFound)?
         .send_command(command)
+        .await?;
+
+    Ok(())
+}
+
+/// Sends the command and waits until the device reports the expected change, returning the new status.
+/// When nothing is expected, the change is worked out from the command itself.
+#[tauri::command]
+pub async fn send_command_confirmed(
+    state: AppStateHandle<'_>,
+    id: String,
+    command: Command,
+    expected: Option<ExpectedStatus>,
+) -> Result<ParsedDeviceStatus, CommandError> {
+    if command.is_destructive() {
+        return Err(DeviceError::ConfirmationRequired.into());
+    }
+    let expected = expected
+        .or_else(|| command.expected_status())
+        .ok_or(InterfaceError::InvalidParameter)?;
+
+    let device = state
+        .read()
         .await
-        .map_err(|_| ())?;
+        .find_device_by_id(&id)
+        .ok_or(DeviceError::DeviceNotFound)?;
+    let status = device
+        .send_command_confirmed(command, expected, ConfirmationPolicy::default())
+        .await?;
+
+    Ok(status.into())
+}
+
+/// Sets the target temperature, switching between cooling, drying and heating to reach it
+#[tauri::command]
+pub async fn set_target_temperature(
+    state: AppStateHandle<'_>,
+    id: String,
+    temp: TempParam,
+) -> Result<(), CommandError> {
+    let (device, config) = {
+        let state = state.read().await;
+        let device = state
+            .find_device_by_id(&id)
+            .ok_or(DeviceError::DeviceNotFound)?;
+        (device, state.db.get_config().unwrap_or_default())
+    };
+    device
+        .set_target_temperature(temp, config.temp_bands())
+        .await?;
 
     Ok(())
 }
 
+/// How a group command went on one of the group's devices
+#[typeshare]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GroupResult {
+    pub id: String,
+    /// Missing when it succeeded
+    pub error: Option<CommandError>,
+}
+
+impl GroupResult {
+    fn from_results(results: Vec<(String, Result<(), DeviceError>)>) -> Vec<GroupResult> {
+        results
+            .into_iter()
+            .map(|(id, result)| GroupResult {
+                id,
+                error: result.err().map(CommandError::from),
+            })
+            .collect()
+    }
+}
+
+#[tauri::command]
+pub async fn get_groups(state: AppStateHandle<'_>) -> Result<Vec<DeviceGroup>, CommandError> {
+    Ok(state.read().await.db.get_groups())
+}
+
+#[tauri::command]
+pub async fn create_group(
+    state: AppStateHandle<'_>,
+    name: String,
+    members: Vec<String>,
+) -> Result<DeviceGroup, CommandError> {
+    let group = DeviceGroup::new(name, members);
+    state.read().await.db.set_group(&group)?;
+    Ok(group)
+}
+
+#[tauri::command]
+pub async fn update_group(
+    state: AppStateHandle<'_>,
+    id: String,
+    name: String,
+    members: Vec<String>,
+) -> Result<DeviceGroup, CommandError> {
+    let state = state.read().await;
+    let mut group = state.db.get_group(&id).ok_or(DeviceError::GroupNotFound)?;
+    group.name = name;
+    group.set_members(members);
+    state.db.set_group(&group)?;
+    Ok(group)
+}
+
+#[tauri::command]
+pub async fn delete_group(state: AppStateHandle<'_>, id: String) -> Result<(), CommandError> {
+    if !state.read().await.db.delete_group(&id)? {
+        return Err(DeviceError::GroupNotFound.into());
+    }
+    Ok(())
+}
+
+/// Sends the command to every device in the group at once.
+/// A device failing doesn't stop the others, so the result for each one is returned
+#[tauri::command]
+pub async fn send_group_command(
+    state: AppStateHandle<'_>,
+    group: String,
+    command: Command,
+) -> Result<Vec<GroupResult>, CommandError> {
+    if command.is_destructive() {
+        return Err(DeviceError::ConfirmationRequired.into());
+    }
+
+    let devices = state.read().await.find_group_devices(&group)?;
+    let results = state::fan_out(devices, |device| {
+        let command = command.clone();
+        async move { device.send_command(command).await }
+    })
+    .await;
+    Ok(GroupResult::from_results(results))
+}
+
+/// Sets the same target temperature on every device in the group, returning the result for each one
+#[tauri::command]
+pub async fn set_group_temperature(
+    state: AppStateHandle<'_>,
+    group: String,
+    temp: TempParam,
+) -> Result<Vec<GroupResult>, CommandError> {
+    let (devices, config) = {
+        let state = state.read().await;
+        let devices = state.find_group_devices(&group)?;
+        (devices, state.db.get_config().unwrap_or_default())
+    };
+    let bands = config.temp_bands();
+    let results = state::fan_out(devices, |device| async move {
+        device.set_target_temperature(temp, bands).await
+    })
+    .await;
+    Ok(GroupResult::from_results(results))
+}
+
 #[tauri::command]
 pub async fn get_status(
     state: AppStateHandle<'_>,
     id: String,
-) -> Result<Option<ParsedDeviceStatus>, ()> {
-    println!("id: {:?}", id);
+) -> Result<Option<ParsedDeviceStatus>, CommandError> {
     let status = state
         .read()
         .await
         .find_device_by_id(&id)
-        .ok_or(())?
+        .ok_or(DeviceError::DeviceNotFound)?
         .get_status()
         .await
         .ok()
         .map(|i| i.into());
     Ok(status)
 }
+
+/// The statuses the device reported between the two times, given as seconds since the unix epoch.
+/// Works for devices that aren't connected anymore too.
+#[tauri::command]
+pub async fn get_history(
+    state: AppStateHandle<'_>,
+    id: String,
+    from: u64,
+    to: u64,
+) -> Result<Vec<StatusSample>, CommandError> {
+    let from = SystemTime::UNIX_EPOCH + Duration::from_secs(from);
+    let to = SystemTime::UNIX_EPOCH + Duration::from_secs(to);
+    Ok(state.read().await.db.get_history(&id, from, to)?)
+}
+
+/// Sums up how the device did over the night that starts on the date, from its recorded history
+#[tauri::command]
+pub async fn get_night_summary(
+    state: AppStateHandle<'_>,
+    id: String,
+    date: NaiveDate,
+) -> Result<NightSummary, CommandError> {
+    let night = Night::new(date).ok_or(InterfaceError::InvalidParameter)?;
+    let samples = state.read().await.db.get_statuses(
+        &id,
+        night.start - NightSummary::MAX_SAMPLE_AGE,
+        night.end,
+    )?;
+    Ok(NightSummary::new(night, &samples))
+}
+
+/// Writes the device's recorded history to the file, with temperatures in the user's unit.
+/// Returns how many samples were written.
+#[tauri::command]
+pub async fn export_history(
+    state: AppStateHandle<'_>,
+    id: String,
+    range: TimeRange,
+    format: ExportFormat,
+    path: PathBuf,
+) -> Result<usize, CommandError> {
+    let db = state.read().await.db.clone();
+    let unit = db.get_config().unwrap_or_default().unit();
+    let count = tokio::task::spawn_blocking(move || -> Result<usize, ExportError> {
+        let samples = db.iter_statuses(&id, range.from, range.to)?;
+        let mut writer = BufWriter::new(File::create(path)?);
+        write_history(&mut writer, samples, format, unit)
+    })
+    .await
+    .map_err(DeviceError::from)??;
+    Ok(count)
+}
+
+#[tauri::command]
+pub async fn get_device_info(
+    state: AppStateHandle<'_>,
+    id: String,
+) -> Result<DeviceInfo, CommandError> {
+    let info = state
+        .read()
+        .await
+        .find_device_by_id(&id)
+        .ok_or(DeviceError::DeviceNotFound)?
+        .get_device_info()
+        .await?;
+
+    Ok(info)
+}
+
+#[tauri::command]
+pub async fn set_device_name(
+    state: AppStateHandle<'_>,
+    id: String,
+    name: String,
+) -> Result<(), CommandError> {
+    let state = state.read().await;
+    state
+        .find_device_by_id(&id)
+        .ok_or(DeviceError::DeviceNotFound)?
+        .set_device_name(&name)
+        .await?;
+    state.db.set_cached_name(&id, &name)?;
+
+    Ok(())
+}
+
+/// The settings that were last applied to the device, or the defaults if they never were
+#[tauri::command]
+pub async fn get_device_settings(
+    state: AppStateHandle<'_>,
+    id: String,
+) -> Result<DeviceSettings, CommandError> {
+    let settings = state.read().await.db.get_settings(&id).unwrap_or_default();
+    Ok(settings)
+}
+
+#[tauri::command]
+pub async fn apply_device_settings(
+    state: AppStateHandle<'_>,
+    id: String,
+    settings: DeviceSettings,
+) -> Result<(), CommandError> {
+    let state = state.read().await;
+    state
+        .find_device_by_id(&id)
+        .ok_or(DeviceError::DeviceNotFound)?
+        .apply_settings(&settings)
+        .await?;
+    state.db.set_settings(&id, &settings)?;
+
+    Ok(())
+}
+
+/// Returns the token that needs to be passed to [`factory_reset`] to confirm the reset
+#[tauri::command]
+pub async fn request_factory_reset(
+    state: AppStateHandle<'_>,
+    id: String,
+) -> Result<String, CommandError> {
+    let token = state.write().await.request_factory_reset(&id)?;
+    Ok(token)
+}
+
+#[tauri::command]
+pub async fn factory_reset(
+    state: AppStateHandle<'_>,
+    id: String,
+    confirmation_token: String,
+) -> Result<(), CommandError> {
+    state
+        .write()
+        .await
+        .factory_reset(&id, &confirmation_token)
+        .await?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_biorhythms(
+    state: AppStateHandle<'_>,
+    id: String,
+) -> Result<Vec<BiorhythmProgram>, CommandError> {
+    let device = state
+        .read()
+        .await
+        .find_device_by_id(&id)
+        .ok_or(DeviceError::DeviceNotFound)?;
+
+    let mut programs = Vec::new();
+    for slot in 1..=3 {
+        programs.push(device.get_biorhythm(slot).await?);
+    }
+
+    Ok(programs)
+}
+
+#[tauri::command]
+pub async fn set_biorhythm(
+    state: AppStateHandle<'_>,
+    id: String,
+    program: BiorhythmProgram,
+) -> Result<(), CommandError> {
+    state
+        .read()
+        .await
+        .find_device_by_id(&id)
+        .ok_or(DeviceError::DeviceNotFound)?
+        .set_biorhythm(&program)
+        .await?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn get_memory_presets(
+    state: AppStateHandle<'_>,
+    id: String,
+) -> Result<Vec<MemoryPreset>, CommandError> {
+    let device = state
+        .read()
+        .await
+        .find_device_by_id(&id)
+        .ok_or(DeviceError::DeviceNotFound)?;
+
+    let mut presets = Vec::new();
+    for slot in 1..=3 {
+        presets.push(device.get_memory_preset(slot).await?);
+    }
+
+    Ok(presets)
+}
+
+#[tauri::command]
+pub async fn store_memory_preset(
+    state: AppStateHandle<'_>,
+    id: String,
+    slot: u8,
+) -> Result<(), CommandError> {
+    state
+        .read()
+        .await
+        .find_device_by_id(&id)
+        .ok_or(DeviceError::DeviceNotFound)?
+        .store_memory_preset(slot)
+        .await?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn rename_memory_preset(
+    state: AppStateHandle<'_>,
+    id: String,
+    slot: u8,
+    name: String,
+) -> Result<(), CommandError> {
+    state
+        .read()
+        .await
+        .find_device_by_id(&id)
+        .ok_or(DeviceError::DeviceNotFound)?
+        .rename_memory_preset(slot, name)
+        .await?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn recall_memory_preset(
+    state: AppStateHandle<'_>,
+    id: String,
+    slot: u8,
+) -> Result<(), CommandError> {
+    state
+        .read()
+        .await
+        .find_device_by_id(&id)
+        .ok_or(DeviceError::DeviceNotFound)?
+        .recall_memory_preset(slot)
+        .await?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn provision_wifi(
+    state: AppStateHandle<'_>,
+    handle: AppHandle,
+    id: String,
+    ssid: String,
+    password: String,
+) -> Result<(), CommandError> {
+    // Don't hold on to the state while the connection test runs
+    let device = state
+        .read()
+        .await
+        .find_device_by_id(&id)
+        .ok_or(DeviceError::DeviceNotFound)?;
+    device
+        .provision_wifi(&ssid, &password, Some(&handle))
+        .await?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn update_firmware(
+    state: AppStateHandle<'_>,
+    handle: AppHandle,
+    id: String,
+) -> Result<FirmwareUpdateOutcome, CommandError> {
+    // Don't hold on to the state while the update runs
+    let device = state
+        .read()
+        .await
+        .find_device_by_id(&id)
+        .ok_or(DeviceError::DeviceNotFound)?;
+    let outcome = device.update_firmware(Some(&handle)).await?;
+
+    Ok(outcome)
+}
+
+/// Every scheduled rule, along with how it went the last time it fired
+#[tauri::command]
+pub async fn get_schedule(state: AppStateHandle<'_>) -> Result<Vec<ScheduleEntry>, CommandError> {
+    let state = state.read().await;
+    let entries = state
+        .db
+        .get_schedule_rules()
+        .into_iter()
+        .map(|rule| ScheduleEntry {
+            last_run: state.db.get_last_run(&rule.id),
+            rule,
+        })
+        .collect();
+    Ok(entries)
+}
+
+#[tauri::command]
+pub async fn create_schedule_rule(
+    state: AppStateHandle<'_>,
+    spec: RuleSpec,
+) -> Result<ScheduleRule, CommandError> {
+    let rule = ScheduleRule::new(spec)?;
+    state.read().await.db.set_schedule_rule(&rule)?;
+    Ok(rule)
+}
+
+/// Changes the rule. It won't fire for times before the edit
+#[tauri::command]
+pub async fn update_schedule_rule(
+    state: AppStateHandle<'_>,
+    id: String,
+    spec: RuleSpec,
+) -> Result<ScheduleRule, CommandError> {
+    let state = state.read().await;
+    let mut rule = state
+        .db
+        .get_schedule_rule(&id)
+        .ok_or(ScheduleError::RuleNotFound)?;
+    rule.edit(spec)?;
+    state.db.set_schedule_rule(&rule)?;
+    Ok(rule)
+}
+
+#[tauri::command]
+pub async fn delete_schedule_rule(
+    state: AppStateHandle<'_>,
+    id: String,
+) -> Result<(), CommandError> {
+    if !state.read().await.db.delete_schedule_rule(&id)? {
+        return Err(ScheduleError::RuleNotFound.into());
+    }
+    Ok(())
+}
@@ -0,0 +1,131 @@
+use std::{
+    io::{self, Write},
+    time::SystemTime,
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use typeshare::typeshare;
+
+use crate::{
+    proto::{DeviceStatus, OperatingMode, ParsedDeviceStatus, ShutDownCode, UpdateStatus},
+    state::{DBError, TemperatureUnit},
+};
+
+#[derive(Error, Debug)]
+pub enum ExportError {
+    #[error("Failed to write the export: {0}")]
+    Io(#[from] io::Error),
+    #[error("Failed to encode a sample: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("{0}")]
+    DBError(#[from] DBError),
+}
+
+#[typeshare]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ExportFormat {
+    Csv,
+    /// One JSON object per line
+    JsonLines,
+}
+
+#[typeshare]
+#[serde_with::serde_as]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TimeRange {
+    /// As seconds since the unix epoch
+    #[typeshare(serialized_as = u64)]
+    #[serde_as(as = "serde_with::TimestampSeconds<i64>")]
+    pub from: SystemTime,
+    /// As seconds since the unix epoch
+    #[typeshare(serialized_as = u64)]
+    #[serde_as(as = "serde_with::TimestampSeconds<i64>")]
+    pub to: SystemTime,
+}
+
+/// One line of the export. Temperatures are in the unit the user picked
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+struct ExportRow {
+    /// As seconds since the unix epoch
+    time: u64,
+    unit: TemperatureUnit,
+    remaining_seconds: u64,
+    actual_temp: f32,
+    target_temp: f32,
+    ambient_temp: f32,
+    operating_mode: OperatingMode,
+    fan_percent: u8,
+    shutdown_code: ShutDownCode,
+    update_status: UpdateStatus,
+}
+
+impl ExportRow {
+    const HEADER: &'static str = "time,unit,remaining_seconds,actual_temp,target_temp,ambient_temp,operating_mode,fan_percent,shutdown_code,update_status";
+
+    fn new(time: SystemTime, status: DeviceStatus, unit: TemperatureUnit) -> ExportRow {
+        let status = ParsedDeviceStatus::from(status);
+        let round = |celsius: f32| (unit.from_celsius(celsius) * 10.0).round() / 10.0;
+        ExportRow {
+            time: time
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs(),
+            unit,
+            remaining_seconds: status.remaining_duration.as_secs(),
+            actual_temp: round(status.actual_temp),
+            target_temp: round(status.target_temp),
+            ambient_temp: round(status.ambient_temp),
+            operating_mode: status.operating_mode,
+            fan_percent: status.fan_step,
+            shutdown_code: status.shutdown_code,
+            update_status: status.update_status,
+        }
+    }
+
+    fn write_csv<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writeln!(
+            writer,
+            "{},{:?},{},{},{},{},{:?},{},{:?},{:?}",
+            self.time,
+            self.unit,
+            self.remaining_seconds,
+            self.actual_temp,
+            self.target_temp,
+            self.ambient_temp,
+            self.operating_mode,
+            self.fan_percent,
+            self.shutdown_code,
+            self.update_status,
+        )
+    }
+}
+
+/// Writes the samples out as they're read, so the whole history never has to be in memory.
+/// Returns how many samples were written.
+pub fn write_history<W: Write>(
+    writer: &mut W,
+    samples: impl IntoIterator<Item = Result<(SystemTime, DeviceStatus), DBError>>,
+    format: ExportFormat,
+    unit: TemperatureUnit,
+) -> Result<usize, ExportError> {
+    if format == ExportFormat::Csv {
+        writeln!(writer, "{}", ExportRow::HEADER)?;
+    }
+
+    let mut count = 0;
+    for sample in samples {
+        let (time, status) = sample?;
+        let row = ExportRow::new(time, status, unit);
+        match format {
+            ExportFormat::Csv => row.write_csv(writer)?,
+            ExportFormat::JsonLines => {
+                serde_json::to_writer(&mut *writer, &row)?;
+                writeln!(writer)?;
+            }
+        }
+        count += 1;
+    }
+    writer.flush()?;
+    Ok(count)
+}
@@ -1,20 +1,35 @@
+pub mod analytics;
 pub mod commands;
+pub mod export;
 pub mod proto;
+pub mod scheduler;
+#[cfg(any(test, feature = "simulator"))]
+pub mod simulator;
 pub mod state;
+pub mod transport;
 
 use std::{error::Error, sync::Arc};
 
 use commands::{
-    connect_device, disconnect_device, get_btle_adapters, get_status, scan_devices, send_command,
+    apply_device_settings, connect_device, create_group, create_schedule_rule, delete_group,
+    delete_schedule_rule, disconnect_device, export_history, factory_reset, get_auto_reconnect,
+    get_biorhythms, get_btle_adapters, get_device_info, get_device_settings, get_faults,
+    get_groups, get_history, get_known_devices, get_memory_presets, get_night_summary,
+    get_schedule, get_status, provision_wifi, recall_memory_preset, rename_memory_preset,
+    request_factory_reset, scan_devices, select_adapter, send_command, send_command_confirmed,
+    send_group_command, set_auto_reconnect, set_biorhythm, set_device_name, set_group_temperature,
+    set_target_temperature, store_memory_preset, update_firmware, update_group,
+    update_schedule_rule,
 };
 use directories::ProjectDirs;
+use scheduler::run_scheduler;
 use state::AppState;
 use tauri::{App, Manager, Runtime, Window, WindowEvent};
 use tokio::sync::RwLock;
 
 use crate::{
     commands::{get_config, set_config},
-    state::handle_events,
+    state::{prune_history, restart_event_task, start_autoconnect, watch_adapters},
 };
 
 pub fn setup_state(app: &mut App) -> Result<(), Box<dyn Error>> {
@@ -27,13 +42,11 @@ pub fn setup_state(app: &mut App) -> Result<(), Box<dyn Error>> {
         let _ = state.scan_devices().await;
         let state = Arc::new(RwLock::new(state));
 
-        let task = {
-            let state = state.clone();
-            tokio::spawn(async move {
-                let _ = handle_events(state).await;
-            })
-        };
-        state.write().await.event_task = Some(task);
+        restart_event_task(&state).await;
+        let _ = start_autoconnect(&state).await;
+        tokio::spawn(watch_adapters(state.clone()));
+        tokio::spawn(prune_history(state.clone()));
+        tokio::spawn(run_scheduler(state.clone()));
         handle.manage(state);
     });
     Ok(())
@@ -54,16 +67,52 @@ pub fn run() {
         .setup(setup_state)
         .invoke_handler(tauri::generate_handler![
             get_btle_adapters,
+            select_adapter,
             scan_devices,
             connect_device,
             disconnect_device,
+            get_known_devices,
+            get_auto_reconnect,
+            set_auto_reconnect,
+            get_faults,
             send_command,
+            send_command_confirmed,
+            set_target_temperature,
+            get_groups,
+            create_group,
+            update_group,
+            delete_group,
+            send_group_command,
+            set_group_temperature,
             get_status,
+            get_history,
+            get_night_summary,
+            export_history,
             get_config,
             set_config,
+            get_device_info,
+            set_device_name,
+            get_device_settings,
+            apply_device_settings,
+            request_factory_reset,
+            factory_reset,
+            get_biorhythms,
+            set_biorhythm,
+            get_memory_presets,
+            store_memory_preset,
+            rename_memory_preset,
+            recall_memory_preset,
+            provision_wifi,
+            update_firmware,
+            get_schedule,
+            create_schedule_rule,
+            update_schedule_rule,
+            delete_schedule_rule,
         ])
         .on_window_event(handle_window_event)
         .plugin(tauri_plugin_store::Builder::default().build())
+        .plugin(tauri_plugin_dialog::init())
+        .plugin(tauri_plugin_notification::init())
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
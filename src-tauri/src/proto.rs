@@ -38,7 +38,9 @@ pub trait Decode: Sized {
 
 #[typeshare]
 #[repr(u8)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive, ToPrimitive, Serialize)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive, ToPrimitive, Serialize, Deserialize,
+)]
 pub enum OperatingMode {
     Standby = 0,
     NormalHeat = 1,
@@ -49,6 +51,28 @@ pub enum OperatingMode {
     Wait = 6,
 }
 
+impl OperatingMode {
+    /// The button that switches the device into this mode, for the modes that can be switched to
+    pub fn button(&self) -> Option<ButtonCode> {
+        match self {
+            OperatingMode::Standby => Some(ButtonCode::Stop),
+            OperatingMode::NormalHeat => Some(ButtonCode::Heat),
+            OperatingMode::TurboHeat => Some(ButtonCode::Turbo),
+            OperatingMode::ExtendedHeat => Some(ButtonCode::ExternalHeat),
+            OperatingMode::Cool => Some(ButtonCode::Cool),
+            OperatingMode::Dry => Some(ButtonCode::Dry),
+            OperatingMode::Wait => None,
+        }
+    }
+
+    pub fn is_heat(&self) -> bool {
+        matches!(
+            self,
+            OperatingMode::NormalHeat | OperatingMode::TurboHeat | OperatingMode::ExtendedHeat
+        )
+    }
+}
+
 #[typeshare]
 #[derive(Debug, Clone, Copy, PartialEq, Serialize)]
 pub struct DeviceStatus {
@@ -106,28 +130,51 @@ impl Decode for DeviceStatus {
     }
 }
 
+impl Encode for DeviceStatus {
+    fn write_to<W: io::Write>(&self, writer: &mut W) -> Result<(), InterfaceError> {
+        // Bytes we don't know the meaning of are left as zero
+        let mut packet = [0u8; 27];
+        packet[3] = self.remaining_hours;
+        packet[4] = self.remaining_minutes;
+        packet[5] = self.remaining_seconds;
+        packet[6] = self.actual_temp;
+        packet[7] = self.target_temp;
+        packet[8] = self.operating_mode as u8;
+        packet[9] = self.fan_step;
+        packet[10] = self.max_duration_hours;
+        packet[11] = self.max_duration_minutes;
+        packet[12] = self.min_target_temp;
+        packet[13] = self.max_target_temp;
+        packet[16] = self.ambient_temp;
+        packet[17] = self.shutdown_code as u8;
+        packet[25] = self.update_status as u8;
+        writer.write_all(&packet)?;
+        Ok(())
+    }
+}
+
 #[typeshare]
 #[serde_with::serde_as]
 #[derive(Debug, Clone, Copy, PartialEq, Serialize)]
 pub struct ParsedDeviceStatus {
     #[typeshare(serialized_as = u64)]
     #[serde_as(as = "serde_with::DurationSeconds<u64>")]
-    remaining_duration: Duration,
+    pub remaining_duration: Duration,
     /// As degrees C
-    actual_temp: f32,
+    pub actual_temp: f32,
     /// As degreesC
-    target_temp: f32,
-    operating_mode: OperatingMode,
+    pub target_temp: f32,
+    pub operating_mode: OperatingMode,
     ///As a percent 0 - 100
-    fan_step: u8,
+    pub fan_step: u8,
     #[typeshare(serialized_as = u64)]
     #[serde_as(as = "serde_with::DurationSeconds<u64>")]
-    max_duration: Duration,
-    min_target_temp: f32,
-    max_target_temp: f32,
-    ambient_temp: f32,
-    shutdown_code: ShutDownCode,
-    update_status: UpdateStatus,
+    pub max_duration: Duration,
+    pub min_target_temp: f32,
+    pub max_target_temp: f32,
+    pub ambient_temp: f32,
+    pub shutdown_code: ShutDownCode,
+    pub update_status: UpdateStatus,
 }
 
 impl From<DeviceStatus> for ParsedDeviceStatus {
@@ -166,7 +213,9 @@ pub struct DeviceStatusEvent {
 
 #[typeshare]
 #[repr(u8)]
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive, ToPrimitive, Serialize)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive, ToPrimitive, Serialize, Deserialize,
+)]
 pub enum ShutDownCode {
     Normal = 0,
     InvalidADC = 1,
@@ -178,6 +227,26 @@ pub enum ShutDownCode {
     ExtenderThermalTrip = 7,
 }
 
+impl ShutDownCode {
+    pub fn is_fault(&self) -> bool {
+        *self != ShutDownCode::Normal
+    }
+
+    /// What went wrong, in words that can be shown to the user
+    pub fn description(&self) -> &'static str {
+        match self {
+            ShutDownCode::Normal => "The device shut down normally",
+            ShutDownCode::InvalidADC => "The temperature sensor gave an invalid reading",
+            ShutDownCode::ThermistorTrackingError => "The temperature sensors disagree",
+            ShutDownCode::FastOverTempTrip => "The device overheated quickly",
+            ShutDownCode::SlowOverTempTrip => "The device overheated",
+            ShutDownCode::FanFailure => "The fan stopped working",
+            ShutDownCode::HeaterPowerStandby => "The heater lost power",
+            ShutDownCode::ExtenderThermalTrip => "The hose extender overheated",
+        }
+    }
+}
+
 #[typeshare]
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive, ToPrimitive, Serialize)]
@@ -202,6 +271,22 @@ pub enum UpdateStatus {
     UpdateFailed = 29,
 }
 
+impl UpdateStatus {
+    /// Whether the status is one the device reports when a connection test or update went wrong
+    pub fn is_failure(&self) -> bool {
+        matches!(
+            self,
+            UpdateStatus::NoWiFiConfig
+                | UpdateStatus::UnableToConnect
+                | UpdateStatus::DHCPFailure
+                | UpdateStatus::UnableToContactServer
+                | UpdateStatus::ConnectionTestFailed
+                | UpdateStatus::RadioDisabled
+                | UpdateStatus::UpdateFailed
+        )
+    }
+}
+
 #[typeshare]
 #[repr(u8)]
 #[derive(
@@ -240,6 +325,94 @@ pub enum ButtonCode {
     SetConfigCompleteFlag = 0x4f,
 }
 
+impl ButtonCode {
+    /// Buttons that wipe out the configuration of the device, and shouldn't be pressed without confirmation
+    pub fn is_destructive(&self) -> bool {
+        matches!(self, ButtonCode::ResetToFactorySettings)
+    }
+
+    /// Recalls one of the three memory slots, numbered 1-3
+    pub fn memory_recall(slot: u8) -> Result<Self, InterfaceError> {
+        match slot {
+            1 => Ok(Self::Memory1Recall),
+            2 => Ok(Self::Memory2Recall),
+            3 => Ok(Self::Memory3Recall),
+            _ => Err(InterfaceError::InvalidParameter),
+        }
+    }
+
+    /// Stores the current state in one of the three memory slots, numbered 1-3
+    pub fn memory_store(slot: u8) -> Result<Self, InterfaceError> {
+        match slot {
+            1 => Ok(Self::Memory1Store),
+            2 => Ok(Self::Memory2Store),
+            3 => Ok(Self::Memory3Store),
+            _ => Err(InterfaceError::InvalidParameter),
+        }
+    }
+
+    /// The mode the device switches to when this button is pressed, for the buttons that change the mode
+    pub fn operating_mode(&self) -> Option<OperatingMode> {
+        match self {
+            ButtonCode::Stop => Some(OperatingMode::Standby),
+            ButtonCode::Cool => Some(OperatingMode::Cool),
+            ButtonCode::Heat => Some(OperatingMode::NormalHeat),
+            ButtonCode::Turbo => Some(OperatingMode::TurboHeat),
+            ButtonCode::Dry => Some(OperatingMode::Dry),
+            ButtonCode::ExternalHeat => Some(OperatingMode::ExtendedHeat),
+            _ => None,
+        }
+    }
+}
+
+#[typeshare]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// The device wide settings that can only be changed with buttons.
+///
+/// The device doesn't report them back, so they're only known after being applied.
+pub struct DeviceSettings {
+    pub ring_light: bool,
+    pub beeper_muted: bool,
+    pub low_power_mode: bool,
+    /// Whether the Wi-Fi/Bluetooth radio stays on
+    pub radio_enabled: bool,
+}
+
+impl Default for DeviceSettings {
+    fn default() -> Self {
+        Self {
+            ring_light: true,
+            beeper_muted: false,
+            low_power_mode: false,
+            radio_enabled: true,
+        }
+    }
+}
+
+impl DeviceSettings {
+    /// The buttons that need to be pressed to apply the settings
+    pub fn buttons(&self) -> [ButtonCode; 4] {
+        [
+            match self.ring_light {
+                true => ButtonCode::EnableRingOfLight,
+                false => ButtonCode::DisableRingOfLight,
+            },
+            match self.beeper_muted {
+                true => ButtonCode::MuteBeeper,
+                false => ButtonCode::UnmuteBeeper,
+            },
+            match self.low_power_mode {
+                true => ButtonCode::SetLowPowerMode,
+                false => ButtonCode::SetNormalPowerMode,
+            },
+            match self.radio_enabled {
+                true => ButtonCode::EnableWiFiBT,
+                false => ButtonCode::DisableWiFiBT,
+            },
+        ]
+    }
+}
+
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive, ToPrimitive)]
 pub enum ParameterCode {
@@ -265,6 +438,37 @@ pub enum ParameterCode {
     FirmwareVersionCodes = 0x20,
 }
 
+impl ParameterCode {
+    /// Every parameter is transferred as a fixed 16 byte block
+    pub const LENGTH: u8 = 0x10;
+
+    /// The name of one of the three memory slots, numbered 1-3
+    pub fn memory_name(slot: u8) -> Result<Self, InterfaceError> {
+        if !(1..=3).contains(&slot) {
+            return Err(InterfaceError::InvalidParameter);
+        }
+        Self::from_u8(Self::MemoryName1 as u8 + slot - 1).ok_or(InterfaceError::InvalidParameter)
+    }
+
+    /// The name of one of the three biorhythm slots, numbered 1-3
+    pub fn biorhythm_name(slot: u8) -> Result<Self, InterfaceError> {
+        if !(1..=3).contains(&slot) {
+            return Err(InterfaceError::InvalidParameter);
+        }
+        Self::from_u8(Self::BiorhythmName1 as u8 + slot - 1).ok_or(InterfaceError::InvalidParameter)
+    }
+
+    /// One of the four fragments of a biorhythm program. Both the slot and fragment are numbered from 1
+    pub fn biorhythm_fragment(slot: u8, fragment: u8) -> Result<Self, InterfaceError> {
+        if !(1..=3).contains(&slot) || !(1..=4).contains(&fragment) {
+            return Err(InterfaceError::InvalidParameter);
+        }
+        let offset = (slot - 1) * 4 + (fragment - 1);
+        Self::from_u8(Self::Biorhythm1Fragment1 as u8 + offset)
+            .ok_or(InterfaceError::InvalidParameter)
+    }
+}
+
 #[typeshare]
 #[repr(u8)]
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, FromPrimitive, ToPrimitive)]
@@ -275,6 +479,7 @@ pub enum CommandClass {
     SetFan = 0x07,
     SetClock = 0x08,
     SetParameter = 0x40,
+    GetParameter = 0x41,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
@@ -287,15 +492,19 @@ pub enum TempParam {
     Fahrenheit(f32),
 }
 
+impl TempParam {
+    pub fn celsius(&self) -> f32 {
+        match self {
+            TempParam::Celsius(val) => *val,
+            TempParam::Fahrenheit(val) => (val - 32.0) * 5.0 / 9.0,
+        }
+    }
+}
+
 impl Encode for TempParam {
     fn write_to<W: io::Write>(&self, writer: &mut W) -> Result<(), InterfaceError> {
         // The actual value we need to write is stored in units of 0.5 Celsius, so we multiply by 2
-        // or convert to Celsius and multiply by 2
-
-        let value = match self {
-            TempParam::Celsius(val) => val * 2.0,
-            TempParam::Fahrenheit(val) => (val - 32.0) * 5.0 / 9.0 * 2.0,
-        };
+        let value = self.celsius() * 2.0;
         let value = value.clamp(0.0, 255.0) as u8;
         writer.write_all(&[value])?;
         Ok(())
@@ -348,6 +557,230 @@ pub enum Command {
     SetParam(SetParamKind),
 }
 
+impl Command {
+    pub fn is_destructive(&self) -> bool {
+        matches!(self, Command::Button(code) if code.is_destructive())
+    }
+
+    /// The class of commands that set an absolute value, where only the newest one sent matters
+    pub fn set_point_class(&self) -> Option<CommandClass> {
+        match self {
+            Command::SetTime { .. } => Some(CommandClass::SetTime),
+            Command::SetTemp(_) => Some(CommandClass::SetTemp),
+            Command::SetFan(_) => Some(CommandClass::SetFan),
+            _ => None,
+        }
+    }
+
+    /// The change to the status that shows the device acted on the command, when there is one to look for
+    pub fn expected_status(&self) -> Option<ExpectedStatus> {
+        match self {
+            Command::Button(code) => code.operating_mode().map(ExpectedStatus::OperatingMode),
+            Command::SetTime { hours, minutes } => Some(ExpectedStatus::RemainingTime {
+                hours: *hours,
+                minutes: *minutes,
+            }),
+            Command::SetTemp(temp) => Some(ExpectedStatus::TargetTemp(*temp)),
+            Command::SetFan(fan) => Some(ExpectedStatus::FanStep(*fan)),
+            Command::SetClock { .. } | Command::SetParam(_) => None,
+        }
+    }
+}
+
+/// A change to the device status that a command is expected to make
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value")]
+#[typeshare]
+pub enum ExpectedStatus {
+    TargetTemp(TempParam),
+    FanStep(FanParam),
+    OperatingMode(OperatingMode),
+    /// The remaining time starts counting down straight away, so this matches for a minute after it's set
+    RemainingTime {
+        hours: u8,
+        minutes: u8,
+    },
+}
+
+/// The range of target temperatures that one mode covers, in degrees C
+#[typeshare]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct TempBand {
+    pub mode: OperatingMode,
+    pub min: f32,
+    pub max: f32,
+}
+
+/// Picks the mode for a target temperature, so that one slider can go all the way from cooling to heating
+#[typeshare]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TempBands {
+    pub bands: Vec<TempBand>,
+}
+
+impl Default for TempBands {
+    fn default() -> Self {
+        Self {
+            bands: vec![
+                TempBand {
+                    mode: OperatingMode::Cool,
+                    min: 19.0,
+                    max: 25.0,
+                },
+                TempBand {
+                    mode: OperatingMode::Dry,
+                    min: 25.0,
+                    max: 30.0,
+                },
+                TempBand {
+                    mode: OperatingMode::ExtendedHeat,
+                    min: 30.0,
+                    max: 33.5,
+                },
+            ],
+        }
+    }
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum BandError {
+    #[error("At least one temperature band is needed")]
+    Empty,
+    #[error("The {0:?} band has to start below where it ends")]
+    InvalidRange(OperatingMode),
+    #[error("{0:?} can't be used for a temperature band")]
+    UnsupportedMode(OperatingMode),
+    #[error("{0:?} is used for more than one band")]
+    DuplicateMode(OperatingMode),
+    #[error("Only one heat mode can be used, but both {0:?} and {1:?} are")]
+    MultipleHeatModes(OperatingMode, OperatingMode),
+    #[error("The {0:?} and {1:?} bands overlap")]
+    Overlap(OperatingMode, OperatingMode),
+    #[error("There's a gap between the {0:?} and {1:?} bands")]
+    Gap(OperatingMode, OperatingMode),
+    #[error("The bands have to stay between {min}°C and {max}°C for this device")]
+    OutOfRange { min: f32, max: f32 },
+}
+
+impl TempBands {
+    /// The modes that a band can use. Only one heat mode can be picked, since they cover the same temperatures
+    pub const MODES: [OperatingMode; 5] = [
+        OperatingMode::Cool,
+        OperatingMode::Dry,
+        OperatingMode::NormalHeat,
+        OperatingMode::TurboHeat,
+        OperatingMode::ExtendedHeat,
+    ];
+
+    /// Checks that the bands are in order from coldest to hottest, cover one unbroken range,
+    /// and use each mode once at most
+    pub fn validate(&self) -> Result<(), BandError> {
+        if self.bands.is_empty() {
+            return Err(BandError::Empty);
+        }
+        for (index, band) in self.bands.iter().enumerate() {
+            if !Self::MODES.contains(&band.mode) {
+                return Err(BandError::UnsupportedMode(band.mode));
+            }
+            if band.min.is_nan() || band.max.is_nan() || band.min >= band.max {
+                return Err(BandError::InvalidRange(band.mode));
+            }
+            for earlier in &self.bands[..index] {
+                if earlier.mode == band.mode {
+                    return Err(BandError::DuplicateMode(band.mode));
+                }
+                if earlier.mode.is_heat() && band.mode.is_heat() {
+                    return Err(BandError::MultipleHeatModes(earlier.mode, band.mode));
+                }
+            }
+        }
+        for (lower, upper) in self.bands.iter().zip(self.bands.iter().skip(1)) {
+            if upper.min < lower.max {
+                return Err(BandError::Overlap(lower.mode, upper.mode));
+            }
+            if upper.min > lower.max {
+                return Err(BandError::Gap(lower.mode, upper.mode));
+            }
+        }
+        Ok(())
+    }
+
+    /// Checks the bands against the target temperature limits the device reports
+    pub fn validate_for(&self, status: &DeviceStatus) -> Result<(), BandError> {
+        self.validate()?;
+        let min = status.min_target_temp as f32 / 2.0;
+        let max = status.max_target_temp as f32 / 2.0;
+        match self.range() {
+            Some((low, high)) if low >= min && high <= max => Ok(()),
+            _ => Err(BandError::OutOfRange { min, max }),
+        }
+    }
+
+    /// The lowest and highest temperatures that the bands cover
+    pub fn range(&self) -> Option<(f32, f32)> {
+        let min = self.bands.iter().map(|i| i.min).reduce(f32::min)?;
+        let max = self.bands.iter().map(|i| i.max).reduce(f32::max)?;
+        Some((min, max))
+    }
+
+    /// The band covering the temperature. Where two bands meet, the first one wins.
+    /// Temperatures outside of the bands are rejected rather than moved into range.
+    pub fn band_for(&self, celsius: f32) -> Result<TempBand, InterfaceError> {
+        self.bands
+            .iter()
+            .find(|i| celsius >= i.min && celsius <= i.max)
+            .copied()
+            .ok_or(InterfaceError::InvalidParameter)
+    }
+
+    /// The commands that take the device from its current status to the target temperature.
+    ///
+    /// When the mode has to change, the remaining time and fan speed are put back the way they were,
+    /// since switching modes resets them.
+    pub fn commands(
+        &self,
+        status: &DeviceStatus,
+        temp: TempParam,
+    ) -> Result<Vec<Command>, InterfaceError> {
+        let band = self.band_for(temp.celsius())?;
+
+        let mut commands = Vec::new();
+        if status.operating_mode != band.mode {
+            let button = band.mode.button().ok_or(InterfaceError::InvalidParameter)?;
+            commands.push(Command::Button(button));
+            if status.remaining_hours != 0 || status.remaining_minutes != 0 {
+                commands.push(Command::SetTime {
+                    hours: status.remaining_hours,
+                    minutes: status.remaining_minutes,
+                });
+            }
+            commands.push(Command::SetFan(FanParam::Step(status.fan_step)));
+        }
+        commands.push(Command::SetTemp(temp));
+
+        Ok(commands)
+    }
+}
+
+impl ExpectedStatus {
+    pub fn matches(&self, status: &DeviceStatus) -> bool {
+        match self {
+            // Compare them the way the device stores them, so rounding doesn't get in the way
+            ExpectedStatus::TargetTemp(temp) => {
+                temp.encode().is_ok_and(|i| i == [status.target_temp])
+            }
+            ExpectedStatus::FanStep(fan) => fan.encode().is_ok_and(|i| i == [status.fan_step]),
+            ExpectedStatus::OperatingMode(mode) => status.operating_mode == *mode,
+            ExpectedStatus::RemainingTime { hours, minutes } => {
+                let expected = *hours as u32 * 60 + *minutes as u32;
+                let remaining =
+                    status.remaining_hours as u32 * 60 + status.remaining_minutes as u32;
+                remaining <= expected && expected - remaining <= 1
+            }
+        }
+    }
+}
+
 impl Encode for Command {
     fn write_to<W: io::Write>(&self, writer: &mut W) -> Result<(), InterfaceError> {
         match self {
@@ -378,34 +811,397 @@ impl Encode for Command {
     }
 }
 
+impl Decode for Command {
+    fn read_from<R: Read>(mut reader: R) -> Result<Self, InterfaceError> {
+        let mut class = [0u8; 1];
+        reader.read_exact(&mut class)?;
+        let class = CommandClass::from_u8(class[0]).ok_or(InterfaceError::InvalidParameter)?;
+
+        let command = match class {
+            CommandClass::Button => {
+                let mut code = [0u8; 1];
+                reader.read_exact(&mut code)?;
+                Command::Button(
+                    ButtonCode::from_u8(code[0]).ok_or(InterfaceError::InvalidParameter)?,
+                )
+            }
+            CommandClass::SetTime => {
+                let mut time = [0u8; 2];
+                reader.read_exact(&mut time)?;
+                Command::SetTime {
+                    hours: time[0],
+                    minutes: time[1],
+                }
+            }
+            // The device only deals in Celsius and fan steps, so those are what we get back
+            CommandClass::SetTemp => {
+                let mut temp = [0u8; 1];
+                reader.read_exact(&mut temp)?;
+                Command::SetTemp(TempParam::Celsius(temp[0] as f32 / 2.0))
+            }
+            CommandClass::SetFan => {
+                let mut step = [0u8; 1];
+                reader.read_exact(&mut step)?;
+                let fan = FanParam::Step(step[0]);
+                fan.validate()?;
+                Command::SetFan(fan)
+            }
+            CommandClass::SetClock => {
+                let mut time = [0u8; 2];
+                reader.read_exact(&mut time)?;
+                Command::SetClock {
+                    hours: time[0],
+                    minutes: time[1],
+                }
+            }
+            CommandClass::SetParameter => Command::SetParam(SetParamKind::read_from(reader)?),
+            // Reading parameters isn't something the frontend can send as a command
+            CommandClass::GetParameter => return Err(InterfaceError::InvalidParameter),
+        };
+
+        Ok(command)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(tag = "type", content = "value")]
 #[typeshare]
 pub enum SetParamKind {
     /// Cannot contain a String longer than 15 bytes.
     DeviceName(String),
+    /// Cannot contain a String longer than 15 bytes. The slot is numbered 1-3
+    MemoryName { slot: u8, name: String },
+    /// Cannot contain a String longer than 15 bytes. The slot is numbered 1-3
+    BiorhythmName { slot: u8, name: String },
+    /// One 16 byte fragment of an encoded [`Biorhythm`]. The slot is numbered 1-3 and the fragment 1-4
+    BiorhythmFragment {
+        slot: u8,
+        fragment: u8,
+        data: Vec<u8>,
+    },
 }
 
-impl Encode for SetParamKind {
-    fn write_to<W: io::Write>(&self, writer: &mut W) -> Result<(), InterfaceError> {
+impl SetParamKind {
+    pub fn code(&self) -> Result<ParameterCode, InterfaceError> {
         match self {
-            SetParamKind::DeviceName(name) => {
-                // Validate that the string is within the allowed limit
-                if name.len() > 15 {
+            SetParamKind::DeviceName(_) => Ok(ParameterCode::DeviceName),
+            SetParamKind::MemoryName { slot, .. } => ParameterCode::memory_name(*slot),
+            SetParamKind::BiorhythmName { slot, .. } => ParameterCode::biorhythm_name(*slot),
+            SetParamKind::BiorhythmFragment { slot, fragment, .. } => {
+                ParameterCode::biorhythm_fragment(*slot, *fragment)
+            }
+        }
+    }
+
+    /// The value of the parameter, before it gets zero padded
+    pub fn value(&self) -> Result<Vec<u8>, InterfaceError> {
+        match self {
+            SetParamKind::DeviceName(name)
+            | SetParamKind::MemoryName { name, .. }
+            | SetParamKind::BiorhythmName { name, .. } => encode_name(name),
+            SetParamKind::BiorhythmFragment { data, .. } => {
+                if data.len() != ParameterCode::LENGTH as usize {
                     return Err(InterfaceError::InvalidParameter);
                 }
-                // Write the header data
-                writer.write_all(&[ParameterCode::DeviceName as u8, 0x10])?;
-                // And then write the string
-                writer.write_all(name.as_bytes())?;
+                Ok(data.clone())
+            }
+        }
+    }
+}
 
-                // Calculate the number of bytes to zero pad with
-                let padding = 16 - name.len();
+impl Encode for SetParamKind {
+    fn write_to<W: io::Write>(&self, writer: &mut W) -> Result<(), InterfaceError> {
+        write_parameter_value(writer, self.code()?, &self.value()?)
+    }
+}
 
-                // And write those bytes out
-                io::copy(&mut io::repeat(0).take(padding as u64), writer)?;
+impl Decode for SetParamKind {
+    fn read_from<R: Read>(mut reader: R) -> Result<Self, InterfaceError> {
+        let mut header = [0u8; 2];
+        reader.read_exact(&mut header)?;
+        let code = ParameterCode::from_u8(header[0]).ok_or(InterfaceError::InvalidParameter)?;
+        if header[1] != ParameterCode::LENGTH {
+            return Err(InterfaceError::InvalidParameter);
+        }
+        let mut value = vec![0u8; ParameterCode::LENGTH as usize];
+        reader.read_exact(&mut value)?;
+
+        let kind = match code {
+            ParameterCode::DeviceName => SetParamKind::DeviceName(read_name(&value[..])?),
+            ParameterCode::MemoryName1
+            | ParameterCode::MemoryName2
+            | ParameterCode::MemoryName3 => SetParamKind::MemoryName {
+                slot: code as u8 - ParameterCode::MemoryName1 as u8 + 1,
+                name: read_name(&value[..])?,
+            },
+            ParameterCode::BiorhythmName1
+            | ParameterCode::BiorhythmName2
+            | ParameterCode::BiorhythmName3 => SetParamKind::BiorhythmName {
+                slot: code as u8 - ParameterCode::BiorhythmName1 as u8 + 1,
+                name: read_name(&value[..])?,
+            },
+            // The firmware version can only be read
+            ParameterCode::FirmwareVersionCodes => return Err(InterfaceError::InvalidParameter),
+            // Everything else is one of the biorhythm fragments
+            _ => {
+                let offset = code as u8 - ParameterCode::Biorhythm1Fragment1 as u8;
+                SetParamKind::BiorhythmFragment {
+                    slot: offset / Biorhythm::FRAGMENTS as u8 + 1,
+                    fragment: offset % Biorhythm::FRAGMENTS as u8 + 1,
+                    data: value,
+                }
             }
+        };
+
+        Ok(kind)
+    }
+}
+
+/// Writes the header of a parameter followed by its zero padded value
+fn write_parameter_value<W: io::Write>(
+    writer: &mut W,
+    code: ParameterCode,
+    value: &[u8],
+) -> Result<(), InterfaceError> {
+    if value.len() > ParameterCode::LENGTH as usize {
+        return Err(InterfaceError::InvalidParameter);
+    }
+    // Write the header data
+    writer.write_all(&[code as u8, ParameterCode::LENGTH])?;
+    // And then write the value
+    writer.write_all(value)?;
+
+    // Calculate the number of bytes to zero pad with
+    let padding = ParameterCode::LENGTH as usize - value.len();
+
+    // And write those bytes out
+    io::copy(&mut io::repeat(0).take(padding as u64), writer)?;
+    Ok(())
+}
+
+/// Converts a name into a parameter value. Names cannot be longer than 15 bytes, since they need to be zero terminated
+pub fn encode_name(name: &str) -> Result<Vec<u8>, InterfaceError> {
+    // Validate that the string is within the allowed limit
+    if name.len() > 15 {
+        return Err(InterfaceError::InvalidParameter);
+    }
+    Ok(name.as_bytes().to_vec())
+}
+
+/// Reads a zero padded name parameter, as written by [`encode_name`]
+pub fn read_name<R: Read>(reader: R) -> Result<String, InterfaceError> {
+    let mut data = Vec::with_capacity(ParameterCode::LENGTH as usize);
+    reader
+        .take(ParameterCode::LENGTH as u64)
+        .read_to_end(&mut data)?;
+    let end = data.iter().position(|i| *i == 0).unwrap_or(data.len());
+    Ok(String::from_utf8_lossy(&data[..end]).to_string())
+}
+
+/// Sets the raw value of a parameter. Values shorter than [`ParameterCode::LENGTH`] are zero padded
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SetParameter<'a> {
+    pub code: ParameterCode,
+    pub value: &'a [u8],
+}
+
+impl Encode for SetParameter<'_> {
+    fn write_to<W: io::Write>(&self, writer: &mut W) -> Result<(), InterfaceError> {
+        writer.write_all(&[CommandClass::SetParameter as u8])?;
+        write_parameter_value(writer, self.code, self.value)
+    }
+}
+
+/// Asks the device to place the value of a parameter in the extended data characteristic
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct GetParameter(pub ParameterCode);
+
+impl Encode for GetParameter {
+    fn write_to<W: io::Write>(&self, writer: &mut W) -> Result<(), InterfaceError> {
+        writer.write_all(&[
+            CommandClass::GetParameter as u8,
+            self.0 as u8,
+            ParameterCode::LENGTH,
+        ])?;
+        Ok(())
+    }
+}
+
+/// The contents of the extended data characteristic, after the device has answered a [`GetParameter`].
+///
+/// It is made up of the code of the parameter, the length of the value and then the value itself.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ParameterResponse {
+    pub code: ParameterCode,
+    pub value: Vec<u8>,
+}
+
+impl Decode for ParameterResponse {
+    fn read_from<R: Read>(mut reader: R) -> Result<Self, InterfaceError> {
+        let mut header = [0u8; 2];
+        reader.read_exact(&mut header)?;
+        let code = ParameterCode::from_u8(header[0]).ok_or(InterfaceError::InvalidParameter)?;
+        if header[1] > ParameterCode::LENGTH {
+            return Err(InterfaceError::InvalidParameter);
         }
+
+        let mut value = vec![0u8; header[1] as usize];
+        reader.read_exact(&mut value)?;
+
+        Ok(Self { code, value })
+    }
+}
+
+#[typeshare]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct VersionCode {
+    pub major: u8,
+    pub minor: u8,
+    pub patch: u8,
+}
+
+#[typeshare]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// The firmware versions reported by the FirmwareVersionCodes parameter
+pub struct FirmwareVersion {
+    /// The main controller that runs the heater and fan
+    pub main: VersionCode,
+    /// The Wi-Fi/Bluetooth module
+    pub radio: VersionCode,
+}
+
+impl Decode for FirmwareVersion {
+    fn read_from<R: Read>(mut reader: R) -> Result<Self, InterfaceError> {
+        let mut packet = [0u8; 6];
+        reader.read_exact(&mut packet)?;
+
+        Ok(Self {
+            main: VersionCode {
+                major: packet[0],
+                minor: packet[1],
+                patch: packet[2],
+            },
+            radio: VersionCode {
+                major: packet[3],
+                minor: packet[4],
+                patch: packet[5],
+            },
+        })
+    }
+}
+
+#[typeshare]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// A single step of a biorhythm program
+pub struct BiorhythmStep {
+    pub operating_mode: OperatingMode,
+    /// Stored in units of 0.5 degrees celsius
+    pub target_temp: u8,
+    /// Represented as a number between 0-19
+    pub fan_step: u8,
+    /// How long the step runs for
+    pub hours: u8,
+    pub minutes: u8,
+}
+
+#[typeshare]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// A program of steps the device runs through one after another.
+///
+/// On the device it is stored as 64 bytes split across four 16 byte fragments.
+/// The first byte is the number of steps, followed by 5 bytes per step and zero padding.
+pub struct Biorhythm {
+    /// Cannot contain more than [`Biorhythm::MAX_STEPS`] steps
+    pub steps: Vec<BiorhythmStep>,
+}
+
+impl Biorhythm {
+    pub const FRAGMENTS: usize = 4;
+    pub const MAX_STEPS: usize = 12;
+    const STEP_LENGTH: usize = 5;
+    const ENCODED_LENGTH: usize = Self::FRAGMENTS * ParameterCode::LENGTH as usize;
+
+    /// Encodes the program and splits it into the fragments the device stores it as
+    pub fn fragments(&self) -> Result<Vec<Vec<u8>>, InterfaceError> {
+        let bytes = self.encode()?;
+        Ok(bytes
+            .chunks(ParameterCode::LENGTH as usize)
+            .map(|i| i.to_vec())
+            .collect())
+    }
+}
+
+impl Encode for Biorhythm {
+    fn write_to<W: io::Write>(&self, writer: &mut W) -> Result<(), InterfaceError> {
+        if self.steps.len() > Self::MAX_STEPS {
+            return Err(InterfaceError::InvalidParameter);
+        }
+        writer.write_all(&[self.steps.len() as u8])?;
+        for step in &self.steps {
+            if step.fan_step > 19 || step.minutes > 59 {
+                return Err(InterfaceError::InvalidParameter);
+            }
+            writer.write_all(&[
+                step.operating_mode as u8,
+                step.target_temp,
+                step.fan_step,
+                step.hours,
+                step.minutes,
+            ])?;
+        }
+
+        let padding = Self::ENCODED_LENGTH - 1 - self.steps.len() * Self::STEP_LENGTH;
+        io::copy(&mut io::repeat(0).take(padding as u64), writer)?;
         Ok(())
     }
 }
+
+impl Decode for Biorhythm {
+    fn read_from<R: Read>(mut reader: R) -> Result<Self, InterfaceError> {
+        let mut packet = [0u8; Self::ENCODED_LENGTH];
+        reader.read_exact(&mut packet)?;
+
+        let count = packet[0] as usize;
+        if count > Self::MAX_STEPS {
+            return Err(InterfaceError::InvalidParameter);
+        }
+
+        let steps = packet[1..]
+            .chunks_exact(Self::STEP_LENGTH)
+            .take(count)
+            .map(|step| {
+                Ok(BiorhythmStep {
+                    operating_mode: OperatingMode::from_u8(step[0])
+                        .ok_or(InterfaceError::InvalidParameter)?,
+                    target_temp: step[1],
+                    fan_step: step[2],
+                    hours: step[3],
+                    minutes: step[4],
+                })
+            })
+            .collect::<Result<Vec<_>, InterfaceError>>()?;
+
+        Ok(Self { steps })
+    }
+}
+
+#[typeshare]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// One of the three named memory presets stored on the device
+pub struct MemoryPreset {
+    /// Numbered 1-3
+    pub slot: u8,
+    /// Cannot contain a String longer than 15 bytes.
+    pub name: String,
+}
+
+#[typeshare]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+/// One of the three named biorhythm programs stored on the device
+pub struct BiorhythmProgram {
+    /// Numbered 1-3
+    pub slot: u8,
+    /// Cannot contain a String longer than 15 bytes.
+    pub name: String,
+    pub biorhythm: Biorhythm,
+}
@@ -0,0 +1,351 @@
+use std::{
+    collections::HashSet,
+    sync::{Arc, Mutex},
+    time::{Duration, SystemTime},
+};
+
+use chrono::{DateTime, Datelike, Local, NaiveDateTime, NaiveTime, TimeZone};
+use futures::future::join_all;
+use serde::{Deserialize, Serialize};
+use tauri::Manager as TauriManager;
+use thiserror::Error;
+use tokio::sync::RwLock;
+use typeshare::typeshare;
+
+use crate::{
+    proto::{Command, TempParam},
+    state::{connect_peripheral, fan_out, AppState, DeviceError},
+};
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum ScheduleError {
+    #[error("Pick at least one day for the rule to run on")]
+    NoDays,
+    #[error("{hour:02}:{minute:02} isn't a time of day")]
+    InvalidTime { hour: u8, minute: u8 },
+    #[error("The rule needs at least one command to send")]
+    NoCommands,
+    #[error("Destructive commands can't be scheduled")]
+    DestructiveCommand,
+    #[error("No rule by the specified ID was found")]
+    RuleNotFound,
+}
+
+#[typeshare]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Day {
+    Monday,
+    Tuesday,
+    Wednesday,
+    Thursday,
+    Friday,
+    Saturday,
+    Sunday,
+}
+
+impl From<chrono::Weekday> for Day {
+    fn from(value: chrono::Weekday) -> Self {
+        match value {
+            chrono::Weekday::Mon => Day::Monday,
+            chrono::Weekday::Tue => Day::Tuesday,
+            chrono::Weekday::Wed => Day::Wednesday,
+            chrono::Weekday::Thu => Day::Thursday,
+            chrono::Weekday::Fri => Day::Friday,
+            chrono::Weekday::Sat => Day::Saturday,
+            chrono::Weekday::Sun => Day::Sunday,
+        }
+    }
+}
+
+/// Fires at the same local time on each of the days
+#[typeshare]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Trigger {
+    pub days: Vec<Day>,
+    pub hour: u8,
+    pub minute: u8,
+}
+
+impl Trigger {
+    /// The latest time the trigger fired, at or before `now`.
+    /// A time that's skipped when the clocks go forward fires when they resume instead
+    pub fn last_fire<Tz: TimeZone>(&self, now: &DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let time = NaiveTime::from_hms_opt(self.hour as u32, self.minute as u32, 0)?;
+        let today = now.date_naive();
+        (0..=7)
+            .filter_map(|days_back| today.checked_sub_days(chrono::Days::new(days_back)))
+            .filter(|date| self.days.contains(&date.weekday().into()))
+            .filter_map(|date| next_valid_local(&now.timezone(), date.and_time(time)))
+            .find(|fire| fire <= now)
+    }
+}
+
+/// The local time, or the first minute after it that exists when it falls in a daylight saving gap
+fn next_valid_local<Tz: TimeZone>(tz: &Tz, local: NaiveDateTime) -> Option<DateTime<Tz>> {
+    // Gaps are at most a few hours, so a day is plenty
+    (0..24 * 60).find_map(|minutes| {
+        tz.from_local_datetime(&(local + chrono::Duration::minutes(minutes)))
+            .earliest()
+    })
+}
+
+#[typeshare]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value")]
+pub enum ScheduleTarget {
+    Device(String),
+    /// Every device in the group, by the group's id
+    Group(String),
+}
+
+#[typeshare]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value")]
+pub enum ScheduleAction {
+    /// Sent in order
+    Commands(Vec<Command>),
+    /// Switches modes to reach the temperature, the same way the temperature slider does
+    TargetTemperature(TempParam),
+}
+
+/// Everything about a rule that the user picks
+#[typeshare]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuleSpec {
+    pub name: String,
+    pub enabled: bool,
+    pub trigger: Trigger,
+    pub target: ScheduleTarget,
+    pub action: ScheduleAction,
+}
+
+impl RuleSpec {
+    pub fn validate(&self) -> Result<(), ScheduleError> {
+        if self.trigger.days.is_empty() {
+            return Err(ScheduleError::NoDays);
+        }
+        if self.trigger.hour > 23 || self.trigger.minute > 59 {
+            return Err(ScheduleError::InvalidTime {
+                hour: self.trigger.hour,
+                minute: self.trigger.minute,
+            });
+        }
+        if let ScheduleAction::Commands(commands) = &self.action {
+            if commands.is_empty() {
+                return Err(ScheduleError::NoCommands);
+            }
+            if commands.iter().any(|i| i.is_destructive()) {
+                return Err(ScheduleError::DestructiveCommand);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[typeshare]
+#[serde_with::serde_as]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleRule {
+    pub id: String,
+    /// When the rule was created or last edited, as seconds since the unix epoch.
+    /// The rule never fires for times before this
+    #[typeshare(serialized_as = u64)]
+    #[serde_as(as = "serde_with::TimestampSeconds<i64>")]
+    pub updated: SystemTime,
+    pub spec: RuleSpec,
+}
+
+/// What the scheduler should do about a rule right now
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Due {
+    NotYet,
+    /// Fire for the scheduled time
+    Run(SystemTime),
+    /// The scheduled time went by too long ago, most likely while the app was closed
+    Missed(SystemTime),
+}
+
+impl ScheduleRule {
+    /// A run that's this late still happens, later than that it's recorded as missed
+    pub const MISSED_RUN_GRACE: Duration = Duration::from_secs(30 * 60);
+
+    pub fn new(spec: RuleSpec) -> Result<ScheduleRule, ScheduleError> {
+        spec.validate()?;
+        Ok(ScheduleRule {
+            id: uuid::Uuid::new_v4().to_string(),
+            updated: SystemTime::now(),
+            spec,
+        })
+    }
+
+    /// Replaces what the user picked, keeping the id
+    pub fn edit(&mut self, spec: RuleSpec) -> Result<(), ScheduleError> {
+        spec.validate()?;
+        self.spec = spec;
+        self.updated = SystemTime::now();
+        Ok(())
+    }
+
+    /// Works out whether the rule should fire, given the time it was last handled for
+    pub fn due<Tz: TimeZone>(&self, now: &DateTime<Tz>, last_handled: Option<SystemTime>) -> Due {
+        if !self.spec.enabled {
+            return Due::NotYet;
+        }
+        let Some(fire) = self.spec.trigger.last_fire(now).map(SystemTime::from) else {
+            return Due::NotYet;
+        };
+        let handled = last_handled.unwrap_or(self.updated).max(self.updated);
+        if fire <= handled {
+            return Due::NotYet;
+        }
+        let late = SystemTime::from(now.clone())
+            .duration_since(fire)
+            .unwrap_or_default();
+        if late <= Self::MISSED_RUN_GRACE {
+            Due::Run(fire)
+        } else {
+            Due::Missed(fire)
+        }
+    }
+}
+
+#[typeshare]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value")]
+pub enum RunOutcome {
+    Succeeded,
+    Failed(String),
+    /// The app wasn't open in time to run it
+    Missed,
+}
+
+#[typeshare]
+#[serde_with::serde_as]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RuleRun {
+    pub rule: String,
+    /// When the rule was meant to fire, as seconds since the unix epoch
+    #[typeshare(serialized_as = u64)]
+    #[serde_as(as = "serde_with::TimestampSeconds<i64>")]
+    pub scheduled: SystemTime,
+    /// When the rule actually ran, as seconds since the unix epoch
+    #[typeshare(serialized_as = u64)]
+    #[serde_as(as = "serde_with::TimestampSeconds<i64>")]
+    pub ran_at: SystemTime,
+    pub outcome: RunOutcome,
+}
+
+/// A rule along with how it went the last time it fired
+#[typeshare]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ScheduleEntry {
+    pub rule: ScheduleRule,
+    pub last_run: Option<RuleRun>,
+}
+
+/// How often the scheduler checks for rules to run
+const SCHEDULER_TICK: Duration = Duration::from_secs(20);
+
+/// Runs the rules as they come due, for as long as the app is open.
+/// Each rule runs in its own task, so one that's slow to connect doesn't hold up the others
+pub async fn run_scheduler(state: Arc<RwLock<AppState>>) {
+    // Rules that are still running, which mustn't be started again before they've saved their run
+    let running = Arc::new(Mutex::new(HashSet::new()));
+    loop {
+        let rules = state.read().await.db.get_schedule_rules();
+        for rule in rules {
+            if running.lock().unwrap().contains(&rule.id) {
+                continue;
+            }
+            let last = state.read().await.db.get_last_run(&rule.id);
+            let (scheduled, missed) = match rule.due(&Local::now(), last.map(|i| i.scheduled)) {
+                Due::NotYet => continue,
+                Due::Run(fire) => (fire, false),
+                Due::Missed(fire) => (fire, true),
+            };
+
+            running.lock().unwrap().insert(rule.id.clone());
+            let state = state.clone();
+            let running = running.clone();
+            tokio::spawn(async move {
+                let outcome = match missed {
+                    true => RunOutcome::Missed,
+                    false => run_rule(&state, &rule).await,
+                };
+                let run = RuleRun {
+                    rule: rule.id.clone(),
+                    scheduled,
+                    ran_at: SystemTime::now(),
+                    outcome,
+                };
+
+                let app = state.read().await;
+                if let Err(err) = app.db.set_last_run(&run) {
+                    println!("Failed to save the run of rule {}: {}", rule.id, err);
+                }
+                let _ = app.handle().emit("ScheduleRun", run);
+                running.lock().unwrap().remove(&rule.id);
+            });
+        }
+        tokio::time::sleep(SCHEDULER_TICK).await;
+    }
+}
+
+/// Connects to each target device first, or reconnects to it, if it isn't connected.
+/// The devices in a group are connected to and run at the same time, and are all run even when some of them fail
+async fn run_rule(state: &Arc<RwLock<AppState>>, rule: &ScheduleRule) -> RunOutcome {
+    let ids = match &rule.spec.target {
+        ScheduleTarget::Device(id) => vec![id.clone()],
+        ScheduleTarget::Group(id) => match state.read().await.db.get_group(id) {
+            Some(group) => group.members,
+            None => return RunOutcome::Failed(DeviceError::GroupNotFound.to_string()),
+        },
+    };
+
+    let devices = join_all(ids.into_iter().map(|id| async move {
+        let device = match connect_peripheral(state, &id).await {
+            Ok(()) => state
+                .read()
+                .await
+                .find_device_by_id(&id)
+                .ok_or(DeviceError::DeviceNotFound),
+            Err(err) => Err(err),
+        };
+        (id, device)
+    }))
+    .await;
+
+    let config = state.read().await.db.get_config().unwrap_or_default();
+    let bands = config.temp_bands();
+    let results = fan_out(devices, |device| async move {
+        match &rule.spec.action {
+            ScheduleAction::Commands(commands) => {
+                for command in commands {
+                    device.send_command(command.clone()).await?;
+                }
+                Ok(())
+            }
+            ScheduleAction::TargetTemperature(temp) => {
+                device.set_target_temperature(*temp, bands).await
+            }
+        }
+    })
+    .await;
+
+    let failures: Vec<String> = results
+        .into_iter()
+        .filter_map(|(id, result)| {
+            let err = result.err()?;
+            Some(match rule.spec.target {
+                // The device is obvious from the rule
+                ScheduleTarget::Device(_) => err.to_string(),
+                ScheduleTarget::Group(_) => format!("{}: {}", id, err),
+            })
+        })
+        .collect();
+    if failures.is_empty() {
+        RunOutcome::Succeeded
+    } else {
+        RunOutcome::Failed(failures.join("; "))
+    }
+}
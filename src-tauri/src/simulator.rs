@@ -0,0 +1,518 @@
+use crate::{
+    proto::{
+        ButtonCode, Command, CommandClass, Decode, DeviceSettings, DeviceStatus, Encode,
+        OperatingMode, ParameterCode, ShutDownCode, UpdateStatus,
+    },
+    state::BedJet,
+    transport::{NotificationStream, Transport},
+};
+use async_trait::async_trait;
+use btleplug::api::{CharPropFlags, Characteristic, ValueNotification, WriteType};
+use futures::channel::mpsc;
+use std::{
+    collections::{BTreeSet, HashMap, HashSet},
+    sync::{Arc, Mutex, MutexGuard},
+    time::Duration,
+};
+use uuid::Uuid;
+
+/// An in-memory BedJet that answers the command characteristic the way the real device does.
+///
+/// Commands update an emulated [`DeviceStatus`], which is sent back out as status notifications.
+/// Clones share the same device, so a test can keep one around to inspect the state while a
+/// [`BedJet`] owns another.
+#[derive(Debug, Clone)]
+pub struct SimulatedBedJet {
+    state: Arc<Mutex<SimulatorState>>,
+}
+
+#[derive(Debug)]
+struct SimulatorState {
+    id: String,
+    connected: bool,
+    /// Whether connection attempts will succeed
+    in_range: bool,
+    /// Whether the Wi-Fi network the device was given can be connected to
+    wifi_reachable: bool,
+    status: DeviceStatus,
+    settings: DeviceSettings,
+    /// Parameter values keyed by their code
+    parameters: HashMap<u8, Vec<u8>>,
+    extended_data: Vec<u8>,
+    memory: [Option<DeviceStatus>; 3],
+    wifi_ssid: Vec<u8>,
+    wifi_password: Vec<u8>,
+    /// Every write to the command characteristic, in the order they were received
+    commands: Vec<Vec<u8>>,
+    subscribed: HashSet<Uuid>,
+    listeners: Vec<mpsc::UnboundedSender<ValueNotification>>,
+}
+
+impl SimulatedBedJet {
+    /// How long the device takes between each update status while running a connection test or update
+    const UPDATE_STEP: Duration = Duration::from_millis(10);
+
+    pub fn new(id: &str, name: &str) -> SimulatedBedJet {
+        let mut state = SimulatorState {
+            id: id.to_string(),
+            connected: false,
+            in_range: true,
+            wifi_reachable: true,
+            status: DeviceStatus {
+                remaining_hours: 0,
+                remaining_minutes: 0,
+                remaining_seconds: 0,
+                actual_temp: 44,
+                target_temp: 44,
+                operating_mode: OperatingMode::Standby,
+                fan_step: 9,
+                max_duration_hours: 0,
+                max_duration_minutes: 0,
+                min_target_temp: 38,
+                max_target_temp: 86,
+                ambient_temp: 42,
+                shutdown_code: ShutDownCode::Normal,
+                update_status: UpdateStatus::Idle,
+            },
+            settings: DeviceSettings::default(),
+            parameters: HashMap::new(),
+            extended_data: Vec::new(),
+            memory: [None; 3],
+            wifi_ssid: Vec::new(),
+            wifi_password: Vec::new(),
+            commands: Vec::new(),
+            subscribed: HashSet::new(),
+            listeners: Vec::new(),
+        };
+        state.reset_parameters(name);
+
+        SimulatedBedJet {
+            state: Arc::new(Mutex::new(state)),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, SimulatorState> {
+        self.state.lock().expect("Simulator state was poisoned")
+    }
+
+    pub fn status(&self) -> DeviceStatus {
+        self.lock().status
+    }
+
+    /// Changes the emulated status directly, as if the device changed it by itself
+    pub fn update_status(&self, update: impl FnOnce(&mut DeviceStatus)) {
+        let mut state = self.lock();
+        update(&mut state.status);
+        state.notify_status();
+    }
+
+    pub fn settings(&self) -> DeviceSettings {
+        self.lock().settings
+    }
+
+    pub fn wifi_credentials(&self) -> (String, String) {
+        let state = self.lock();
+        (
+            String::from_utf8_lossy(&state.wifi_ssid).to_string(),
+            String::from_utf8_lossy(&state.wifi_password).to_string(),
+        )
+    }
+
+    /// Every write to the command characteristic, in the order they were received
+    pub fn commands(&self) -> Vec<Vec<u8>> {
+        self.lock().commands.clone()
+    }
+
+    /// Drops the connection. Like the real device, this forgets every subscription
+    pub fn drop_connection(&self) {
+        let mut state = self.lock();
+        state.connected = false;
+        state.subscribed.clear();
+        state.listeners.clear();
+    }
+
+    /// Makes the Wi-Fi network unreachable, or reachable again, for connection tests and updates
+    pub fn set_wifi_reachable(&self, reachable: bool) {
+        self.lock().wifi_reachable = reachable;
+    }
+
+    /// Moves the device out of range, or back into it. Connecting fails while it's out of range
+    pub fn set_in_range(&self, in_range: bool) {
+        let mut state = self.lock();
+        state.in_range = in_range;
+        drop(state);
+        if !in_range {
+            self.drop_connection();
+        }
+    }
+
+    fn characteristic(uuid: Uuid, properties: CharPropFlags) -> Characteristic {
+        Characteristic {
+            uuid,
+            service_uuid: BedJet::SERVICE_UUID,
+            properties,
+            descriptors: BTreeSet::new(),
+        }
+    }
+
+    /// Walks the update status through each step, like the device does while it's busy with Wi-Fi
+    fn run_update_statuses(&self, statuses: Vec<UpdateStatus>) {
+        let device = self.clone();
+        tokio::spawn(async move {
+            for status in statuses {
+                tokio::time::sleep(Self::UPDATE_STEP).await;
+                device.update_status(|i| i.update_status = status);
+            }
+        });
+    }
+
+    fn handle_command(&self, data: &[u8]) {
+        let mut state = self.lock();
+        state.commands.push(data.to_vec());
+
+        // Parameter requests aren't a Command, since the frontend never sends them
+        if let [class, code, ..] = data {
+            if *class == CommandClass::GetParameter as u8 {
+                let value = state
+                    .parameters
+                    .get(code)
+                    .cloned()
+                    .unwrap_or_else(|| vec![0; ParameterCode::LENGTH as usize]);
+                state.extended_data = [*code, value.len() as u8]
+                    .into_iter()
+                    .chain(value)
+                    .collect();
+                return;
+            }
+        }
+
+        // The device ignores anything it doesn't understand
+        let Ok(command) = Command::read_from(data) else {
+            return;
+        };
+        match command {
+            Command::Button(button) => {
+                if let Some(statuses) = state.press_button(button) {
+                    // The device reports its status as soon as it takes the command, before it has started
+                    state.notify_status();
+                    drop(state);
+                    self.run_update_statuses(statuses);
+                    return;
+                }
+            }
+            Command::SetTime { hours, minutes } => {
+                state.status.remaining_hours = hours;
+                state.status.remaining_minutes = minutes;
+                state.status.remaining_seconds = 0;
+            }
+            Command::SetTemp(temp) => {
+                let [temp] = temp.encode().unwrap_or_default()[..] else {
+                    return;
+                };
+                state.status.target_temp =
+                    temp.clamp(state.status.min_target_temp, state.status.max_target_temp);
+            }
+            Command::SetFan(fan) => {
+                let [step] = fan.encode().unwrap_or_default()[..] else {
+                    return;
+                };
+                state.status.fan_step = step;
+            }
+            Command::SetClock { .. } => {}
+            Command::SetParam(kind) => {
+                if let (Ok(code), Ok(value)) = (kind.code(), kind.value()) {
+                    state.parameters.insert(code as u8, value);
+                }
+            }
+        }
+        state.notify_status();
+    }
+}
+
+impl SimulatorState {
+    fn reset_parameters(&mut self, name: &str) {
+        self.parameters.clear();
+        self.parameters
+            .insert(ParameterCode::DeviceName as u8, name.as_bytes().to_vec());
+        for slot in 1..=3 {
+            let memory = format!("Memory {}", slot);
+            let biorhythm = format!("Biorhythm {}", slot);
+            if let Ok(code) = ParameterCode::memory_name(slot) {
+                self.parameters.insert(code as u8, memory.into_bytes());
+            }
+            if let Ok(code) = ParameterCode::biorhythm_name(slot) {
+                self.parameters.insert(code as u8, biorhythm.into_bytes());
+            }
+        }
+        self.parameters.insert(
+            ParameterCode::FirmwareVersionCodes as u8,
+            vec![3, 1, 4, 2, 0, 7],
+        );
+    }
+
+    fn name(&self) -> Vec<u8> {
+        let name = self
+            .parameters
+            .get(&(ParameterCode::DeviceName as u8))
+            .cloned()
+            .unwrap_or_default();
+        // Names are stored zero padded, but the friendly name characteristic doesn't include the padding
+        name.into_iter().take_while(|i| *i != 0).collect()
+    }
+
+    /// Applies a button press, returning the update statuses to walk through if it started a Wi-Fi operation
+    fn press_button(&mut self, button: ButtonCode) -> Option<Vec<UpdateStatus>> {
+        match button {
+            ButtonCode::Stop => self.set_mode(OperatingMode::Standby),
+            ButtonCode::Cool => self.set_mode(OperatingMode::Cool),
+            ButtonCode::Heat => self.set_mode(OperatingMode::NormalHeat),
+            ButtonCode::Turbo => self.set_mode(OperatingMode::TurboHeat),
+            ButtonCode::Dry => self.set_mode(OperatingMode::Dry),
+            ButtonCode::ExternalHeat => self.set_mode(OperatingMode::ExtendedHeat),
+            ButtonCode::FanUp => {
+                self.status.fan_step = self.status.fan_step.saturating_add(1).min(19)
+            }
+            ButtonCode::FanDown => self.status.fan_step = self.status.fan_step.saturating_sub(1),
+            ButtonCode::TempUp1C
+            | ButtonCode::TempDown1C
+            | ButtonCode::TempUp1F
+            | ButtonCode::TempDown1F => {
+                let status = &mut self.status;
+                let target = match button {
+                    ButtonCode::TempUp1C => status.target_temp.saturating_add(2),
+                    ButtonCode::TempDown1C => status.target_temp.saturating_sub(2),
+                    ButtonCode::TempUp1F => status.target_temp.saturating_add(1),
+                    _ => status.target_temp.saturating_sub(1),
+                };
+                status.target_temp = target.clamp(status.min_target_temp, status.max_target_temp);
+            }
+            ButtonCode::Memory1Store | ButtonCode::Memory2Store | ButtonCode::Memory3Store => {
+                let slot = button as usize - ButtonCode::Memory1Store as usize;
+                self.memory[slot] = Some(self.status);
+            }
+            ButtonCode::Memory1Recall | ButtonCode::Memory2Recall | ButtonCode::Memory3Recall => {
+                let slot = button as usize - ButtonCode::Memory1Recall as usize;
+                if let Some(memory) = self.memory[slot] {
+                    self.set_mode(memory.operating_mode);
+                    let status = &mut self.status;
+                    status.target_temp = memory.target_temp;
+                    status.fan_step = memory.fan_step;
+                    status.remaining_hours = memory.remaining_hours;
+                    status.remaining_minutes = memory.remaining_minutes;
+                    status.remaining_seconds = memory.remaining_seconds;
+                }
+            }
+            ButtonCode::StartConnectionTest => {
+                return Some(match (self.wifi_ssid.is_empty(), self.wifi_reachable) {
+                    (true, _) => vec![
+                        UpdateStatus::Starting,
+                        UpdateStatus::NoWiFiConfig,
+                        UpdateStatus::ConnectionTestFailed,
+                    ],
+                    (false, false) => vec![
+                        UpdateStatus::Starting,
+                        UpdateStatus::ConnectingToAP,
+                        UpdateStatus::UnableToConnect,
+                        UpdateStatus::ConnectionTestFailed,
+                    ],
+                    (false, true) => vec![
+                        UpdateStatus::Starting,
+                        UpdateStatus::ConnectingToAP,
+                        UpdateStatus::GotIPAddress,
+                        UpdateStatus::CheckingConnection,
+                        UpdateStatus::ConnectionTestOK,
+                    ],
+                });
+            }
+            ButtonCode::StartFirmwareUpdate => {
+                return Some(match (self.wifi_ssid.is_empty(), self.wifi_reachable) {
+                    (true, _) => vec![UpdateStatus::Starting, UpdateStatus::NoWiFiConfig],
+                    (false, false) => vec![
+                        UpdateStatus::Starting,
+                        UpdateStatus::ConnectingToAP,
+                        UpdateStatus::UnableToConnect,
+                    ],
+                    (false, true) => vec![
+                        UpdateStatus::Starting,
+                        UpdateStatus::ConnectingToAP,
+                        UpdateStatus::GotIPAddress,
+                        UpdateStatus::CheckingForUpdate,
+                        UpdateStatus::NoUpdateNeeded,
+                    ],
+                });
+            }
+            ButtonCode::SetLowPowerMode => self.settings.low_power_mode = true,
+            ButtonCode::SetNormalPowerMode => self.settings.low_power_mode = false,
+            ButtonCode::EnableRingOfLight => self.settings.ring_light = true,
+            ButtonCode::DisableRingOfLight => self.settings.ring_light = false,
+            ButtonCode::MuteBeeper => self.settings.beeper_muted = true,
+            ButtonCode::UnmuteBeeper => self.settings.beeper_muted = false,
+            ButtonCode::EnableWiFiBT => self.settings.radio_enabled = true,
+            ButtonCode::DisableWiFiBT => self.settings.radio_enabled = false,
+            ButtonCode::ResetToFactorySettings => {
+                self.reset_parameters("BEDJET_V3");
+                self.settings = DeviceSettings::default();
+                self.memory = [None; 3];
+                self.wifi_ssid.clear();
+                self.wifi_password.clear();
+                self.set_mode(OperatingMode::Standby);
+            }
+            ButtonCode::SetConfigCompleteFlag => {}
+        }
+        None
+    }
+
+    /// Switches mode, resetting the runtime to the maximum for the new mode
+    fn set_mode(&mut self, mode: OperatingMode) {
+        // Approximations of the limits the real device reports
+        let (hours, minutes) = match mode {
+            OperatingMode::Standby | OperatingMode::Wait => (0, 0),
+            OperatingMode::NormalHeat => (4, 0),
+            OperatingMode::TurboHeat => (0, 10),
+            OperatingMode::ExtendedHeat => (10, 0),
+            OperatingMode::Cool | OperatingMode::Dry => (12, 0),
+        };
+
+        let status = &mut self.status;
+        status.operating_mode = mode;
+        status.max_duration_hours = hours;
+        status.max_duration_minutes = minutes;
+        status.remaining_hours = hours;
+        status.remaining_minutes = minutes;
+        status.remaining_seconds = 0;
+    }
+
+    fn notify_status(&mut self) {
+        if !self.connected || !self.subscribed.contains(&BedJet::DEVICE_STATUS_UUID) {
+            return;
+        }
+
+        // A leading zero marks the notification as containing the whole status
+        let mut value = vec![0];
+        if self.status.write_to(&mut value).is_err() {
+            return;
+        }
+        self.listeners.retain(|listener| {
+            listener
+                .unbounded_send(ValueNotification {
+                    uuid: BedJet::DEVICE_STATUS_UUID,
+                    value: value.clone(),
+                })
+                .is_ok()
+        });
+    }
+}
+
+#[async_trait]
+impl Transport for SimulatedBedJet {
+    fn id(&self) -> String {
+        self.lock().id.clone()
+    }
+
+    async fn is_connected(&self) -> Result<bool, btleplug::Error> {
+        Ok(self.lock().connected)
+    }
+
+    async fn connect(&self) -> Result<(), btleplug::Error> {
+        let mut state = self.lock();
+        if !state.in_range {
+            return Err(btleplug::Error::DeviceNotFound);
+        }
+        state.connected = true;
+        Ok(())
+    }
+
+    async fn disconnect(&self) -> Result<(), btleplug::Error> {
+        self.drop_connection();
+        Ok(())
+    }
+
+    async fn discover_services(&self) -> Result<(), btleplug::Error> {
+        match self.lock().connected {
+            true => Ok(()),
+            false => Err(btleplug::Error::NotConnected),
+        }
+    }
+
+    fn characteristics(&self) -> BTreeSet<Characteristic> {
+        let read_write = CharPropFlags::READ | CharPropFlags::WRITE;
+        [
+            Self::characteristic(
+                BedJet::DEVICE_STATUS_UUID,
+                CharPropFlags::READ | CharPropFlags::NOTIFY,
+            ),
+            Self::characteristic(BedJet::FRIENDLY_NAME_UUID, CharPropFlags::READ),
+            Self::characteristic(BedJet::WIFI_SSID_UUID, read_write),
+            Self::characteristic(BedJet::WIFI_PASSWORD_UUID, CharPropFlags::WRITE),
+            Self::characteristic(
+                BedJet::COMMANDS_UUID,
+                CharPropFlags::WRITE | CharPropFlags::WRITE_WITHOUT_RESPONSE,
+            ),
+            Self::characteristic(BedJet::EXTENDED_DATA_UUID, read_write),
+        ]
+        .into_iter()
+        .collect()
+    }
+
+    async fn read(&self, characteristic: &Characteristic) -> Result<Vec<u8>, btleplug::Error> {
+        let state = self.lock();
+        if !state.connected {
+            return Err(btleplug::Error::NotConnected);
+        }
+
+        match characteristic.uuid {
+            BedJet::DEVICE_STATUS_UUID => Ok(state.status.encode().unwrap_or_default()),
+            BedJet::FRIENDLY_NAME_UUID => Ok(state.name()),
+            BedJet::WIFI_SSID_UUID => Ok(state.wifi_ssid.clone()),
+            BedJet::EXTENDED_DATA_UUID => Ok(state.extended_data.clone()),
+            _ => Err(btleplug::Error::NotSupported(
+                "Characteristic can't be read".to_string(),
+            )),
+        }
+    }
+
+    async fn write(
+        &self,
+        characteristic: &Characteristic,
+        data: &[u8],
+        _write_type: WriteType,
+    ) -> Result<(), btleplug::Error> {
+        if !self.lock().connected {
+            return Err(btleplug::Error::NotConnected);
+        }
+
+        match characteristic.uuid {
+            BedJet::COMMANDS_UUID => self.handle_command(data),
+            BedJet::WIFI_SSID_UUID => self.lock().wifi_ssid = data.to_vec(),
+            BedJet::WIFI_PASSWORD_UUID => self.lock().wifi_password = data.to_vec(),
+            _ => {
+                return Err(btleplug::Error::NotSupported(
+                    "Characteristic can't be written".to_string(),
+                ))
+            }
+        }
+        Ok(())
+    }
+
+    async fn subscribe(&self, characteristic: &Characteristic) -> Result<(), btleplug::Error> {
+        let mut state = self.lock();
+        state.subscribed.insert(characteristic.uuid);
+        // The device sends out its status as soon as something listens to it
+        state.notify_status();
+        Ok(())
+    }
+
+    async fn unsubscribe(&self, characteristic: &Characteristic) -> Result<(), btleplug::Error> {
+        self.lock().subscribed.remove(&characteristic.uuid);
+        Ok(())
+    }
+
+    async fn notifications(&self) -> Result<NotificationStream, btleplug::Error> {
+        let (send, recv) = mpsc::unbounded();
+        let mut state = self.lock();
+        state.listeners.push(send);
+        // The real device sends its status out periodically, so new listeners don't wait long for it
+        state.notify_status();
+        Ok(Box::pin(recv))
+    }
+}
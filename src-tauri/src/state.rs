@@ -1,4 +1,11 @@
-use crate::proto::{Command, Decode, DeviceStatus, Encode, InterfaceError, ParsedDeviceStatus};
+use crate::proto::{
+    encode_name, read_name, BandError, Biorhythm, BiorhythmProgram, ButtonCode, Command,
+    CommandClass, Decode, DeviceSettings, DeviceStatus, Encode, ExpectedStatus, FirmwareVersion,
+    GetParameter, InterfaceError, MemoryPreset, ParameterCode, ParameterResponse,
+    ParsedDeviceStatus, SetParameter, ShutDownCode, TempBands, TempParam, UpdateStatus,
+};
+use crate::scheduler::{RuleRun, ScheduleRule};
+use crate::transport::Transport;
 use btleplug::{
     api::{
         Central, CentralEvent, Characteristic, Manager as ManagerTrait,
@@ -9,16 +16,19 @@ use btleplug::{
 use futures::{Future, FutureExt, Stream, StreamExt};
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
+    cmp::Reverse,
+    collections::{HashMap, HashSet},
     io::{Cursor, Read},
     pin::Pin,
     sync::Arc,
     task::Poll,
+    time::{Duration, Instant, SystemTime},
 };
 use tauri::{AppHandle, Manager as TauriManager};
+use tauri_plugin_notification::NotificationExt;
 use thiserror::Error;
 use tokio::{
-    sync::{watch, Mutex, RwLock},
+    sync::{broadcast, watch, Mutex, RwLock},
     task::{JoinError, JoinHandle},
 };
 use typeshare::typeshare;
@@ -38,37 +48,131 @@ pub enum DeviceError {
     MissingCharacteristic,
     #[error("No Device by the specified Peripheral ID was found")]
     DeviceNotFound,
+    #[error("Timed out waiting for the device to respond")]
+    Timeout,
+    #[error("The device failed its Wi-Fi connection test: {0:?}")]
+    WifiTestFailed(UpdateStatus),
+    #[error("The firmware update failed: {0:?}")]
+    FirmwareUpdateFailed(UpdateStatus),
+    #[error("The command needs to be confirmed before it can be sent")]
+    ConfirmationRequired,
+    #[error("The confirmation token is invalid or has expired")]
+    InvalidConfirmation,
+    #[error("Gave up trying to reconnect to the device")]
+    ReconnectFailed,
+    #[error("No Bluetooth adapter by the specified name was found")]
+    AdapterNotFound,
+    #[error("No Bluetooth adapter is available")]
+    NoAdapter,
+    #[error("The device didn't apply the command after {attempts} attempts")]
+    NotAcknowledged { attempts: u32 },
+    #[error("{0}")]
+    DBError(#[from] DBError),
+    #[error("{0}")]
+    InvalidBands(#[from] BandError),
+    #[error("No group by the specified ID was found")]
+    GroupNotFound,
+}
+
+#[derive(Error, Debug)]
+pub enum DBError {
+    #[error("Failed to access the database: {0}")]
+    Sled(#[from] sled::Error),
+    #[error("Failed to encode the value for the database: {0}")]
+    Encode(#[from] rmp_serde::encode::Error),
+    #[error("Failed to read a recorded device status: {0}")]
+    Status(#[from] InterfaceError),
 }
 
 pub struct AppState {
     handle: AppHandle,
-    btle_manager: Manager,
-    pub selected_adapter: Adapter,
+    /// Missing when the platform's Bluetooth stack couldn't be reached
+    btle_manager: Option<Manager>,
+    /// Missing until an adapter is plugged in
+    selected_adapter: Option<Adapter>,
     pub event_task: Option<tokio::task::JoinHandle<()>>,
     pub all_adapters: Vec<Adapter>,
     connected_devices: Vec<BedJet>,
+    /// Outstanding factory reset confirmations, keyed by device id
+    factory_reset_tokens: HashMap<String, (String, Instant)>,
+    /// Running reconnect supervisors, keyed by device id
+    reconnect_tasks: HashMap<String, JoinHandle<()>>,
+    pub reconnect_policy: ReconnectPolicy,
+    /// Remembered devices that will be connected to as soon as the scan finds them
+    pending_autoconnect: HashSet<String>,
     pub db: DBState,
 }
 
 impl AppState {
+    /// How long a factory reset confirmation token stays valid
+    const FACTORY_RESET_TOKEN_LIFETIME: Duration = Duration::from_secs(60);
+
+    /// How often to check for adapters being plugged in
+    const ADAPTER_POLL_INTERVAL: Duration = Duration::from_secs(5);
+    /// How often to throw away history that's past its retention
+    const HISTORY_PRUNE_INTERVAL: Duration = Duration::from_secs(60 * 60);
+    /// How often to record the status even when it hasn't changed
+    const HISTORY_HEARTBEAT: Duration = Duration::from_secs(60);
+
     pub async fn new(handle: AppHandle, db: sled::Db) -> AppState {
-        let manager = btleplug::platform::Manager::new().await.unwrap();
-        let adapters = manager.adapters().await.unwrap();
-        let value = AppState {
+        let manager = Manager::new().await.ok();
+        let adapters = match &manager {
+            Some(manager) => manager.adapters().await.unwrap_or_default(),
+            None => Vec::new(),
+        };
+        let db = DBState::new(db);
+        let selected_adapter = preferred_adapter(&db, &adapters).await;
+
+        AppState {
             handle,
             btle_manager: manager,
-            selected_adapter: adapters.first().unwrap().clone(),
+            selected_adapter,
             event_task: None,
             all_adapters: adapters,
             connected_devices: Vec::new(),
-            db: DBState::new(db),
+            factory_reset_tokens: HashMap::new(),
+            reconnect_tasks: HashMap::new(),
+            reconnect_policy: ReconnectPolicy::default(),
+            pending_autoconnect: HashSet::new(),
+            db,
+        }
+    }
+
+    pub fn adapter(&self) -> Result<&Adapter, DeviceError> {
+        self.selected_adapter.as_ref().ok_or(DeviceError::NoAdapter)
+    }
+
+    /// Looks for adapters that were plugged in since the last time, and picks one if there wasn't one to use.
+    /// Returns true when a new adapter was selected.
+    async fn refresh_adapters(&mut self) -> bool {
+        if self.btle_manager.is_none() {
+            self.btle_manager = Manager::new().await.ok();
+        }
+        let Some(manager) = &self.btle_manager else {
+            return false;
         };
+        let Ok(adapters) = manager.adapters().await else {
+            return false;
+        };
+        self.all_adapters = adapters;
 
-        value
+        if self.selected_adapter.is_some() {
+            return false;
+        }
+        self.selected_adapter = preferred_adapter(&self.db, &self.all_adapters).await;
+        self.selected_adapter.is_some()
     }
 
     async fn set_adapter(&mut self, adapter: Adapter) {
-        self.selected_adapter = adapter;
+        if let Some(old) = self.selected_adapter.replace(adapter) {
+            let _ = old.stop_scan().await;
+        }
+
+        // The devices on the old adapter can't be reached through the new one
+        for (_, task) in self.reconnect_tasks.drain() {
+            task.abort();
+        }
+        self.pending_autoconnect.clear();
 
         //Disconnect all the disconnected devices
         let mut connected_devices = self.connected_devices.clone();
@@ -78,56 +182,122 @@ impl AppState {
         self.connected_devices.clear();
     }
 
-    pub async fn scan_devices(&self) -> Result<(), btleplug::Error> {
-        self.selected_adapter
+    pub async fn scan_devices(&self) -> Result<(), DeviceError> {
+        self.adapter()?
             .start_scan(ScanFilter {
                 services: vec![BedJet::SERVICE_UUID],
             })
-            .await
+            .await?;
+        Ok(())
+    }
+
+    pub async fn get_peripherals(&self) -> Result<Vec<Peripheral>, DeviceError> {
+        Ok(self.adapter()?.peripherals().await?)
     }
 
-    pub async fn get_peripherals(&self) -> Result<Vec<Peripheral>, btleplug::Error> {
-        self.selected_adapter.peripherals().await
+    pub fn handle(&self) -> &AppHandle {
+        &self.handle
     }
 
     pub fn find_device_by_id(&self, id: &str) -> Option<BedJet> {
         self.connected_devices.iter().find(|i| i.id == id).cloned()
     }
 
-    pub async fn connect_peripheral(&mut self, id: &str) -> Result<(), DeviceError> {
-        let device = self.find_device_by_id(id);
-        if let Some(device) = device {
-            let is_connected = device.peripheral.is_connected().await?;
-            if !is_connected {
-                device.connect(Some(self.handle.clone())).await?;
+    /// Each member of the group, along with the device if it's connected
+    pub fn find_group_devices(&self, group_id: &str) -> Result<GroupDevices, DeviceError> {
+        let group = self
+            .db
+            .get_group(group_id)
+            .ok_or(DeviceError::GroupNotFound)?;
+        Ok(group
+            .members
+            .into_iter()
+            .map(|id| {
+                let device = self
+                    .find_device_by_id(&id)
+                    .ok_or(DeviceError::DeviceNotFound);
+                (id, device)
+            })
+            .collect())
+    }
+
+    /// Checks the bands against the limits of every connected device that has reported its status
+    pub fn validate_bands(&self, bands: &TempBands) -> Result<(), BandError> {
+        bands.validate()?;
+        for device in &self.connected_devices {
+            if let Some(status) = device.current_status() {
+                bands.validate_for(&status)?;
             }
-            return Ok(());
         }
-
-        let peripherals = self.get_peripherals().await?;
-        let peripheral = peripherals
-            .iter()
-            .find(|i| i.id().to_string() == id)
-            .cloned()
-            .ok_or(DeviceError::DeviceNotFound)?;
-
-        let bedjet = BedJet::new(peripheral, Some(self.handle.clone())).await?;
-        bedjet.listen_status().await?;
-        let name = bedjet.get_friendly_name().await?;
-        self.db.set_cached_name(id, &name);
-
-        self.connected_devices.push(bedjet);
-        println!("Successfully added device");
         Ok(())
     }
 
     pub async fn disconnect_peripheral(&mut self, id: &str) {
+        self.stop_reconnecting(id);
         let device = self.find_device_by_id(id);
         if let Some(device) = device {
             let _ = device.disconnect().await;
         }
         self.connected_devices.retain(|i| i.id != id);
     }
+
+    /// The remembered devices that should be connected to on startup, according to the user's preference
+    pub fn autoconnect_targets(&self) -> Vec<String> {
+        let known = self.db.get_known_devices();
+        let preference = self.db.get_config().unwrap_or_default().autoconnect;
+        match preference {
+            AutoconnectPreference::All => known.into_iter().map(|i| i.id).collect(),
+            AutoconnectPreference::LastUsed => known.into_iter().take(1).map(|i| i.id).collect(),
+            AutoconnectPreference::None => Vec::new(),
+        }
+    }
+
+    pub fn set_auto_reconnect(&mut self, id: &str, enabled: bool) -> Result<(), DBError> {
+        self.db.set_auto_reconnect(id, enabled)?;
+        if !enabled {
+            self.stop_reconnecting(id);
+        }
+        Ok(())
+    }
+
+    fn stop_reconnecting(&mut self, id: &str) {
+        if let Some(task) = self.reconnect_tasks.remove(id) {
+            task.abort();
+        }
+    }
+
+    /// Hands out a token that has to be passed to [`AppState::factory_reset`] to confirm the reset
+    pub fn request_factory_reset(&mut self, id: &str) -> Result<String, DeviceError> {
+        self.find_device_by_id(id)
+            .ok_or(DeviceError::DeviceNotFound)?;
+
+        let token = Uuid::new_v4().to_string();
+        self.factory_reset_tokens
+            .insert(id.to_string(), (token.clone(), Instant::now()));
+        Ok(token)
+    }
+
+    pub async fn factory_reset(&mut self, id: &str, token: &str) -> Result<(), DeviceError> {
+        // Tokens can only be used once, whether or not they match
+        let (expected, issued) = self
+            .factory_reset_tokens
+            .remove(id)
+            .ok_or(DeviceError::InvalidConfirmation)?;
+        if expected != token || issued.elapsed() > Self::FACTORY_RESET_TOKEN_LIFETIME {
+            return Err(DeviceError::InvalidConfirmation);
+        }
+
+        let device = self
+            .find_device_by_id(id)
+            .ok_or(DeviceError::DeviceNotFound)?;
+        device
+            .send_command(Command::Button(ButtonCode::ResetToFactorySettings))
+            .await?;
+
+        // The device forgets its name and settings, so we should too
+        self.db.clear_device(id)?;
+        Ok(())
+    }
 }
 
 #[typeshare]
@@ -138,6 +308,206 @@ pub struct PeripheralResult {
     pub connected: bool,
 }
 
+#[typeshare]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DeviceInfo {
+    pub id: String,
+    pub name: String,
+    pub firmware: FirmwareVersion,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", content = "value")]
+#[typeshare]
+pub enum WifiProvisioningProgress {
+    /// The SSID and password have been written to the device
+    CredentialsWritten,
+    /// The device reported a new status while running the connection test
+    Testing(UpdateStatus),
+    Succeeded,
+    /// Contains the last error the device reported before the test failed
+    Failed(UpdateStatus),
+    TimedOut,
+}
+
+#[typeshare]
+#[derive(Debug, Clone, Serialize)]
+pub struct WifiProvisioningEvent {
+    pub id: String,
+    pub progress: WifiProvisioningProgress,
+}
+
+/// Devices that can be sent the same commands at once, like both sides of a bed
+#[typeshare]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeviceGroup {
+    pub id: String,
+    pub name: String,
+    /// The ids of the devices in the group
+    pub members: Vec<String>,
+}
+
+impl DeviceGroup {
+    pub fn new(name: String, members: Vec<String>) -> DeviceGroup {
+        let mut group = DeviceGroup {
+            id: Uuid::new_v4().to_string(),
+            name,
+            members: Vec::new(),
+        };
+        group.set_members(members);
+        group
+    }
+
+    /// Keeps the order the members were given in, without duplicates
+    pub fn set_members(&mut self, members: Vec<String>) {
+        self.members.clear();
+        for member in members {
+            if !self.members.contains(&member) {
+                self.members.push(member);
+            }
+        }
+    }
+}
+
+#[typeshare]
+#[serde_with::serde_as]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct KnownDevice {
+    pub id: String,
+    pub name: Option<String>,
+    /// As seconds since the unix epoch
+    #[typeshare(serialized_as = u64)]
+    #[serde_as(as = "serde_with::TimestampSeconds<i64>")]
+    pub last_connected: SystemTime,
+}
+
+/// The device started reporting a fault, after running normally
+#[typeshare]
+#[serde_with::serde_as]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DeviceFault {
+    pub id: String,
+    pub code: ShutDownCode,
+    /// As seconds since the unix epoch
+    #[typeshare(serialized_as = u64)]
+    #[serde_as(as = "serde_with::TimestampSeconds<i64>")]
+    pub time: SystemTime,
+}
+
+/// A status the device reported, and when it reported it
+#[typeshare]
+#[serde_with::serde_as]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct StatusSample {
+    /// As seconds since the unix epoch
+    #[typeshare(serialized_as = u64)]
+    #[serde_as(as = "serde_with::TimestampSeconds<i64>")]
+    pub time: SystemTime,
+    pub status: ParsedDeviceStatus,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", content = "value")]
+#[typeshare]
+pub enum AutoconnectProgress {
+    /// Waiting for the scan to find the device
+    Waiting,
+    Connecting,
+    Connected,
+    /// Contains the reason the connection failed
+    Failed(String),
+}
+
+#[typeshare]
+#[derive(Debug, Clone, Serialize)]
+pub struct AutoconnectEvent {
+    pub id: String,
+    pub progress: AutoconnectProgress,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "type", content = "value")]
+#[typeshare]
+pub enum FirmwareUpdateProgress {
+    /// The device is joining the network
+    Connecting,
+    Checking,
+    Updating,
+    Restarting,
+    Done,
+    UpToDate,
+    Failed(UpdateStatus),
+    TimedOut,
+}
+
+#[typeshare]
+#[derive(Debug, Clone, Serialize)]
+pub struct FirmwareUpdateEvent {
+    pub id: String,
+    pub progress: FirmwareUpdateProgress,
+}
+
+#[typeshare]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum FirmwareUpdateOutcome {
+    /// The update was installed and the device is restarting
+    Updated,
+    UpToDate,
+}
+
+/// How long to wait between attempts to get a dropped device back, and when to stop trying
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReconnectPolicy {
+    pub initial_delay: Duration,
+    pub max_delay: Duration,
+    pub max_attempts: u32,
+}
+
+impl ReconnectPolicy {
+    /// The delay before the given attempt, doubling each time up to `max_delay`
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2u32.saturating_pow(attempt.saturating_sub(1));
+        self.initial_delay
+            .checked_mul(factor)
+            .unwrap_or(self.max_delay)
+            .min(self.max_delay)
+    }
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+            max_attempts: 20,
+        }
+    }
+}
+
+/// How long [`BedJet::send_command_confirmed`] waits for the status to change, and how many times it sends the command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConfirmationPolicy {
+    pub attempts: u32,
+    /// How long to wait for the status after each attempt
+    pub timeout: Duration,
+}
+
+impl Default for ConfirmationPolicy {
+    fn default() -> Self {
+        Self {
+            attempts: 3,
+            timeout: Duration::from_secs(2),
+        }
+    }
+}
+
+#[typeshare]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ReconnectEvent {
+    pub id: String,
+    pub attempt: u32,
+}
+
 #[derive(Debug, Clone, Serialize)]
 #[serde(tag = "type", content = "value")]
 #[typeshare]
@@ -145,13 +515,18 @@ enum DeviceEvent {
     Discovered(PeripheralResult),
     Disconnected(PeripheralResult),
     Connected(PeripheralResult),
+    /// Waiting to make another attempt at reconnecting to a dropped device
+    Reconnecting(ReconnectEvent),
+    Reconnected(ReconnectEvent),
+    /// Every reconnect attempt failed, and the device has been forgotten
+    GaveUp(ReconnectEvent),
 }
 
-pub async fn handle_events(state: Arc<RwLock<AppState>>) -> Result<(), btleplug::Error> {
+pub async fn handle_events(state: Arc<RwLock<AppState>>) -> Result<(), DeviceError> {
     let (handle, mut events) = {
         let state = state.read().await;
         let handle = state.handle.clone();
-        let events = state.selected_adapter.events().await?;
+        let events = state.adapter()?.events().await?;
         (handle, events)
     };
 
@@ -161,23 +536,25 @@ pub async fn handle_events(state: Arc<RwLock<AppState>>) -> Result<(), btleplug:
                 let id = id.to_string();
                 let name = { state.read().await.db.get_cached_name(&id) };
                 let event = DeviceEvent::Discovered(PeripheralResult {
-                    id,
+                    id: id.clone(),
                     name,
                     connected: false,
                 });
                 println!("Emitting: {:#?}", event);
                 let _ = handle.emit("DeviceEvent", event);
+                tokio::spawn(autoconnect(state.clone(), id));
             }
             CentralEvent::DeviceDisconnected(id) => {
                 let id = id.to_string();
                 let name = { state.read().await.db.get_cached_name(&id) };
                 let event = DeviceEvent::Disconnected(PeripheralResult {
-                    id,
+                    id: id.clone(),
                     name,
                     connected: false,
                 });
                 println!("Emitting: {:#?}", event);
                 let _ = handle.emit("DeviceEvent", event);
+                supervise_reconnect(&state, &id).await;
             }
             CentralEvent::DeviceConnected(id) => {
                 let id = id.to_string();
@@ -190,72 +567,758 @@ pub async fn handle_events(state: Arc<RwLock<AppState>>) -> Result<(), btleplug:
                 println!("Emitting: {:#?}", event);
                 let _ = handle.emit("DeviceEvent", event);
             }
-            _ => {}
-        };
+            _ => {}
+        };
+    }
+
+    Ok(())
+}
+
+/// The adapter that was picked last time if it's still around, otherwise the first one
+async fn preferred_adapter(db: &DBState, adapters: &[Adapter]) -> Option<Adapter> {
+    let saved = db.get_config().unwrap_or_default().adapter;
+    match find_adapter(adapters, &saved).await {
+        Some(adapter) => Some(adapter),
+        None => adapters.first().cloned(),
+    }
+}
+
+async fn find_adapter(adapters: &[Adapter], name: &str) -> Option<Adapter> {
+    for adapter in adapters {
+        if adapter.adapter_info().await.is_ok_and(|info| info == name) {
+            return Some(adapter.clone());
+        }
+    }
+    None
+}
+
+/// Listens to the events of the selected adapter, replacing whatever was listening before
+pub async fn restart_event_task(state: &Arc<RwLock<AppState>>) {
+    let mut app = state.write().await;
+    if let Some(task) = app.event_task.take() {
+        task.abort();
+    }
+
+    let inner = state.clone();
+    app.event_task = Some(tokio::spawn(async move {
+        let _ = handle_events(inner).await;
+    }));
+}
+
+/// Switches over to a different adapter and remembers it for next time.
+/// Everything connected through the old adapter gets disconnected, and the new one starts scanning.
+pub async fn switch_adapter(state: &Arc<RwLock<AppState>>, name: &str) -> Result<(), DeviceError> {
+    {
+        let mut app = state.write().await;
+        let adapter = find_adapter(&app.all_adapters, name)
+            .await
+            .ok_or(DeviceError::AdapterNotFound)?;
+        app.set_adapter(adapter).await;
+
+        let mut config = app.db.get_config().unwrap_or_default();
+        config.adapter = name.to_string();
+        app.db.set_config(&config)?;
+    }
+
+    restart_event_task(state).await;
+    state.read().await.scan_devices().await?;
+    Ok(())
+}
+
+/// Keeps an eye out for adapters being plugged in.
+/// When there wasn't an adapter to use before, this attaches to the new one and starts scanning with it.
+pub async fn watch_adapters(state: Arc<RwLock<AppState>>) {
+    loop {
+        tokio::time::sleep(AppState::ADAPTER_POLL_INTERVAL).await;
+        if !state.write().await.refresh_adapters().await {
+            continue;
+        }
+
+        restart_event_task(&state).await;
+        let app = state.read().await;
+        let _ = app.scan_devices().await;
+        if let Ok(adapter) = app.adapter() {
+            let name = adapter.adapter_info().await.unwrap_or_default();
+            let _ = app.handle.emit("AdapterAttached", name);
+        }
+        drop(app);
+        let _ = start_autoconnect(&state).await;
+    }
+}
+
+/// Queues up the remembered devices to be connected to as the scan finds them,
+/// and connects to any of them that have already been found
+pub async fn start_autoconnect(state: &Arc<RwLock<AppState>>) -> Result<(), DeviceError> {
+    let peripherals = {
+        let mut app = state.write().await;
+        let targets = app.autoconnect_targets();
+        for id in &targets {
+            let _ = app.handle.emit(
+                "AutoconnectEvent",
+                AutoconnectEvent {
+                    id: id.clone(),
+                    progress: AutoconnectProgress::Waiting,
+                },
+            );
+        }
+        app.pending_autoconnect.extend(targets);
+        app.get_peripherals().await?
+    };
+
+    for peripheral in peripherals {
+        let id = PeripheralTrait::id(&peripheral).to_string();
+        tokio::spawn(autoconnect(state.clone(), id));
+    }
+    Ok(())
+}
+
+/// Connects to the device, or reconnects to it if it dropped.
+/// The state is only locked to look the device up and to add it, so a slow connection doesn't hold up every other command
+pub async fn connect_peripheral(
+    state: &Arc<RwLock<AppState>>,
+    id: &str,
+) -> Result<(), DeviceError> {
+    let (handle, device) = {
+        let app = state.read().await;
+        (app.handle.clone(), app.find_device_by_id(id))
+    };
+    if let Some(device) = device {
+        if !device.peripheral.is_connected().await? {
+            state.write().await.stop_reconnecting(id);
+            device.resume(Some(handle)).await?;
+        }
+        return Ok(());
+    }
+
+    let peripherals = state.read().await.get_peripherals().await?;
+    let peripheral = peripherals
+        .into_iter()
+        .find(|i| PeripheralTrait::id(i).to_string() == id)
+        .ok_or(DeviceError::DeviceNotFound)?;
+    let bedjet = BedJet::new(peripheral, Some(handle.clone())).await?;
+    let name = match bedjet.listen_status().await {
+        Ok(()) => bedjet.get_friendly_name().await,
+        Err(err) => Err(err.into()),
+    };
+    let name = match name {
+        Ok(name) => name,
+        Err(err) => {
+            bedjet.stop_notifications().await;
+            return Err(err);
+        }
+    };
+
+    let mut app = state.write().await;
+    // Connected to from somewhere else in the meantime. It's the same peripheral, so it's left connected,
+    // but only the device that's kept should be handling its notifications
+    if app.find_device_by_id(id).is_some() {
+        bedjet.stop_notifications().await;
+        return Ok(());
+    }
+    tokio::spawn(record_history(
+        app.db.clone(),
+        bedjet.id.clone(),
+        bedjet.subscribe_status(),
+    ));
+    tokio::spawn(record_faults(
+        app.db.clone(),
+        Some(handle),
+        bedjet.subscribe_faults(),
+    ));
+    app.db.set_cached_name(id, &name)?;
+    app.db.remember_device(id)?;
+
+    app.connected_devices.push(bedjet);
+    println!("Successfully added device");
+    Ok(())
+}
+
+/// Connects to the device if it's waiting to be autoconnected
+async fn autoconnect(state: Arc<RwLock<AppState>>, id: String) {
+    let handle = {
+        let mut app = state.write().await;
+        if !app.pending_autoconnect.remove(&id) {
+            return;
+        }
+        app.handle.clone()
+    };
+
+    let emit = |progress| {
+        let event = AutoconnectEvent {
+            id: id.clone(),
+            progress,
+        };
+        let _ = handle.emit("AutoconnectEvent", event);
+    };
+    emit(AutoconnectProgress::Connecting);
+    match connect_peripheral(&state, &id).await {
+        Ok(()) => emit(AutoconnectProgress::Connected),
+        Err(err) => emit(AutoconnectProgress::Failed(err.to_string())),
+    }
+}
+
+/// Starts trying to get a dropped device back, unless that's been turned off for it
+async fn supervise_reconnect(state: &Arc<RwLock<AppState>>, id: &str) {
+    let mut app = state.write().await;
+    // Devices that were disconnected on purpose are already gone from the list
+    let Some(device) = app.find_device_by_id(id) else {
+        return;
+    };
+    if !app.db.get_auto_reconnect(id)
+        || app
+            .reconnect_tasks
+            .get(id)
+            .is_some_and(|task| !task.is_finished())
+    {
+        return;
+    }
+
+    let policy = app.reconnect_policy;
+    let handle = app.handle.clone();
+    let state = state.clone();
+    let task = tokio::spawn(async move {
+        if device.reconnect(policy, Some(&handle)).await.is_err() {
+            // Forget the device so that it can be connected again from scratch
+            let mut app = state.write().await;
+            app.connected_devices.retain(|i| i.id != device.id);
+            app.reconnect_tasks.remove(&device.id);
+        }
+    });
+    app.reconnect_tasks.insert(id.to_string(), task);
+}
+
+/// The id of each device to act on, along with the device or the reason it can't be used
+pub type GroupDevices = Vec<(String, Result<BedJet, DeviceError>)>;
+
+/// Runs the action on every device at the same time, so a slow or missing device doesn't hold up the others.
+/// Returns the result for each device, in the order they were given.
+pub async fn fan_out<F, Fut>(
+    devices: GroupDevices,
+    action: F,
+) -> Vec<(String, Result<(), DeviceError>)>
+where
+    F: Fn(BedJet) -> Fut,
+    Fut: Future<Output = Result<(), DeviceError>>,
+{
+    let tasks = devices.into_iter().map(|(id, device)| {
+        let run = device.map(&action);
+        async move {
+            let result = match run {
+                Ok(run) => run.await,
+                Err(err) => Err(err),
+            };
+            (id, result)
+        }
+    });
+    futures::future::join_all(tasks).await
+}
+
+/// Picks out the statuses worth saving to the history: ones that are different from the last one saved, apart from the countdown,
+/// which changes every second while the device runs.
+/// An unchanged status is saved again now and then, so gaps in the history show when the device wasn't connected.
+#[derive(Debug, Default)]
+pub struct HistoryFilter {
+    last: Option<(DeviceStatus, SystemTime)>,
+}
+
+impl HistoryFilter {
+    /// Whether the status reported at the time should be saved. Assumes it will be, if so
+    pub fn should_record(&mut self, time: SystemTime, status: &DeviceStatus) -> bool {
+        let without_countdown = |status: DeviceStatus| DeviceStatus {
+            remaining_hours: 0,
+            remaining_minutes: 0,
+            remaining_seconds: 0,
+            ..status
+        };
+        if self.last.is_some_and(|(prev, saved)| {
+            without_countdown(prev) == without_countdown(*status)
+                && time
+                    .duration_since(saved)
+                    .is_ok_and(|i| i < AppState::HISTORY_HEARTBEAT)
+        }) {
+            return false;
+        }
+        self.last = Some((*status, time));
+        true
+    }
+}
+
+/// Saves the statuses the device reports that [`HistoryFilter`] picks out.
+/// Runs until the device is dropped.
+pub async fn record_history(
+    db: DBState,
+    id: String,
+    mut recv: watch::Receiver<Option<DeviceStatus>>,
+) {
+    let mut filter = HistoryFilter::default();
+    while recv.changed().await.is_ok() {
+        let status = *recv.borrow_and_update();
+        let Some(status) = status else {
+            continue;
+        };
+        let time = SystemTime::now();
+        if !filter.should_record(time, &status) {
+            continue;
+        }
+        if let Err(err) = db.record_status(&id, time, &status) {
+            println!("Failed to record the status of {}: {}", id, err);
+        }
+    }
+}
+
+/// Saves each fault the device reports to the fault log, and shows it as a notification
+pub async fn record_faults(
+    db: DBState,
+    handle: Option<AppHandle>,
+    mut recv: broadcast::Receiver<DeviceFault>,
+) {
+    loop {
+        let fault = match recv.recv().await {
+            Ok(fault) => fault,
+            Err(broadcast::error::RecvError::Lagged(_)) => continue,
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+        if let Err(err) = db.log_fault(&fault) {
+            println!("Failed to log the fault of {}: {}", fault.id, err);
+        }
+        if let Some(handle) = &handle {
+            let name = db.get_cached_name(&fault.id).unwrap_or(fault.id.clone());
+            let _ = handle
+                .notification()
+                .builder()
+                .title(format!("{} stopped", name))
+                .body(fault.code.description())
+                .show();
+        }
+    }
+}
+
+/// Every so often, throws away the history that's older than the user wants to keep
+pub async fn prune_history(state: Arc<RwLock<AppState>>) {
+    loop {
+        {
+            let app = state.read().await;
+            let retention = app.db.get_config().unwrap_or_default().history_retention();
+            if let Some(cutoff) = retention.and_then(|i| SystemTime::now().checked_sub(i)) {
+                if let Err(err) = app.db.prune_history(cutoff) {
+                    println!("Failed to prune the status history: {}", err);
+                }
+            }
+        }
+        tokio::time::sleep(AppState::HISTORY_PRUNE_INTERVAL).await;
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DBState {
+    db: sled::Db,
+}
+
+impl DBState {
+    pub const DEVICE_KEY: &'static str = "devices";
+    pub const CONFIG_KEY: &'static str = "config";
+    pub const SETTINGS_KEY: &'static str = "settings";
+    pub const RECONNECT_KEY: &'static str = "reconnect";
+    pub const KNOWN_DEVICE_KEY: &'static str = "known";
+    /// Each device's history is kept in its own tree, keyed by big endian milliseconds since the unix epoch
+    pub const HISTORY_KEY: &'static str = "history";
+    /// The fault log is one tree for every device, keyed by big endian milliseconds since the unix epoch and then a sequence number
+    pub const FAULT_KEY: &'static str = "faults";
+    pub const SCHEDULE_KEY: &'static str = "schedule";
+    pub const SCHEDULE_RUN_KEY: &'static str = "schedule_run";
+    pub const GROUP_KEY: &'static str = "group";
+
+    pub fn new(db: sled::Db) -> DBState {
+        DBState { db }
+    }
+    pub fn flush(&self) -> Result<usize, sled::Error> {
+        self.db.flush()
+    }
+    pub fn get_cached_name(&self, id: &str) -> Option<String> {
+        self.db
+            .get(format!("{}:{}", Self::DEVICE_KEY, id))
+            .ok()
+            .flatten()
+            .as_deref()
+            .map(String::from_utf8_lossy)
+            .map(|i| i.to_string())
+    }
+
+    pub fn set_cached_name(&self, id: &str, name: &str) -> Result<(), DBError> {
+        self.db
+            .insert(format!("{}:{}", Self::DEVICE_KEY, id), name)?;
+        Ok(())
+    }
+
+    pub fn get_config(&self) -> Option<UserPreferences> {
+        self.db
+            .get(Self::CONFIG_KEY)
+            .ok()
+            .flatten()
+            .as_deref()
+            .and_then(|i| rmp_serde::from_slice(i).ok())
+    }
+    pub fn set_config(&self, config: &UserPreferences) -> Result<(), DBError> {
+        println!("Setting config: {:#?}", config);
+        let data = rmp_serde::to_vec(config)?;
+        self.db.insert(Self::CONFIG_KEY, data)?;
+        Ok(())
+    }
+
+    /// The settings that were last applied to the device
+    pub fn get_settings(&self, id: &str) -> Option<DeviceSettings> {
+        self.db
+            .get(format!("{}:{}", Self::SETTINGS_KEY, id))
+            .ok()
+            .flatten()
+            .as_deref()
+            .and_then(|i| rmp_serde::from_slice(i).ok())
+    }
+
+    /// Forgets everything cached about the device
+    pub fn clear_device(&self, id: &str) -> Result<(), DBError> {
+        self.db.remove(format!("{}:{}", Self::DEVICE_KEY, id))?;
+        self.db.remove(format!("{}:{}", Self::SETTINGS_KEY, id))?;
+        Ok(())
+    }
+
+    pub fn set_settings(&self, id: &str, settings: &DeviceSettings) -> Result<(), DBError> {
+        let data = rmp_serde::to_vec(settings)?;
+        self.db
+            .insert(format!("{}:{}", Self::SETTINGS_KEY, id), data)?;
+        Ok(())
+    }
+
+    /// Every device that has been connected to, most recently connected first
+    pub fn get_known_devices(&self) -> Vec<KnownDevice> {
+        let mut devices: Vec<KnownDevice> = self
+            .db
+            .scan_prefix(format!("{}:", Self::KNOWN_DEVICE_KEY))
+            .values()
+            .filter_map(|i| i.ok())
+            .filter_map(|i| rmp_serde::from_slice(&i).ok())
+            .collect();
+        devices.sort_by_key(|i| Reverse(i.last_connected));
+        devices
+    }
+
+    /// Adds the device to the known devices, or marks it as connected just now
+    pub fn remember_device(&self, id: &str) -> Result<(), DBError> {
+        let device = KnownDevice {
+            id: id.to_string(),
+            name: self.get_cached_name(id),
+            last_connected: SystemTime::now(),
+        };
+        let data = rmp_serde::to_vec(&device)?;
+        self.db
+            .insert(format!("{}:{}", Self::KNOWN_DEVICE_KEY, id), data)?;
+        Ok(())
+    }
+
+    /// Whether to reconnect to the device when it drops. Defaults to on
+    pub fn get_auto_reconnect(&self, id: &str) -> bool {
+        self.db
+            .get(format!("{}:{}", Self::RECONNECT_KEY, id))
+            .ok()
+            .flatten()
+            .as_deref()
+            .and_then(|i| rmp_serde::from_slice(i).ok())
+            .unwrap_or(true)
+    }
+
+    pub fn set_auto_reconnect(&self, id: &str, enabled: bool) -> Result<(), DBError> {
+        let data = rmp_serde::to_vec(&enabled)?;
+        self.db
+            .insert(format!("{}:{}", Self::RECONNECT_KEY, id), data)?;
+        Ok(())
+    }
+
+    fn history_tree(&self, id: &str) -> Result<sled::Tree, DBError> {
+        Ok(self.db.open_tree(format!("{}:{}", Self::HISTORY_KEY, id))?)
+    }
+
+    fn history_key(time: SystemTime) -> [u8; 8] {
+        let millis = time
+            .duration_since(SystemTime::UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as u64;
+        millis.to_be_bytes()
+    }
+
+    /// Stores the status the way the device sends it
+    pub fn record_status(
+        &self,
+        id: &str,
+        time: SystemTime,
+        status: &DeviceStatus,
+    ) -> Result<(), DBError> {
+        // The sequence number keeps statuses recorded in the same millisecond apart
+        let mut key = Self::history_key(time).to_vec();
+        key.extend_from_slice(&self.db.generate_id()?.to_be_bytes());
+        let data = status.encode()?;
+        self.history_tree(id)?.insert(key, data)?;
+        Ok(())
+    }
+
+    /// Reads the statuses the device reported between the two times one at a time, oldest first
+    pub fn iter_statuses(
+        &self,
+        id: &str,
+        from: SystemTime,
+        to: SystemTime,
+    ) -> Result<impl Iterator<Item = Result<(SystemTime, DeviceStatus), DBError>>, DBError> {
+        // Keys are the time followed by a sequence number, so the end has to take in every sequence number
+        let mut end = Self::history_key(to).to_vec();
+        end.extend_from_slice(&u64::MAX.to_be_bytes());
+        let range = self
+            .history_tree(id)?
+            .range(Self::history_key(from).to_vec()..=end);
+        Ok(range.map(|entry| {
+            let (key, value) = entry?;
+            let millis = key
+                .get(..8)
+                .and_then(|i| i.try_into().ok())
+                .map(u64::from_be_bytes)
+                .unwrap_or_default();
+            let status = DeviceStatus::read_from(Cursor::new(value))?;
+            Ok((
+                SystemTime::UNIX_EPOCH + Duration::from_millis(millis),
+                status,
+            ))
+        }))
+    }
+
+    /// The statuses the device reported between the two times, as the device sent them, oldest first
+    pub fn get_statuses(
+        &self,
+        id: &str,
+        from: SystemTime,
+        to: SystemTime,
+    ) -> Result<Vec<(SystemTime, DeviceStatus)>, DBError> {
+        self.iter_statuses(id, from, to)?.collect()
+    }
+
+    /// The statuses the device reported between the two times, oldest first
+    pub fn get_history(
+        &self,
+        id: &str,
+        from: SystemTime,
+        to: SystemTime,
+    ) -> Result<Vec<StatusSample>, DBError> {
+        Ok(self
+            .get_statuses(id, from, to)?
+            .into_iter()
+            .map(|(time, status)| StatusSample {
+                time,
+                status: status.into(),
+            })
+            .collect())
+    }
+
+    pub fn log_fault(&self, fault: &DeviceFault) -> Result<(), DBError> {
+        // The sequence number keeps faults logged in the same millisecond apart
+        let mut key = Self::history_key(fault.time).to_vec();
+        key.extend_from_slice(&self.db.generate_id()?.to_be_bytes());
+        let data = rmp_serde::to_vec(fault)?;
+        self.db.open_tree(Self::FAULT_KEY)?.insert(key, data)?;
+        Ok(())
+    }
+
+    /// The logged faults, most recent first. Only the device's faults if an id is given
+    pub fn get_faults(&self, id: Option<&str>) -> Result<Vec<DeviceFault>, DBError> {
+        let mut faults = Vec::new();
+        for value in self.db.open_tree(Self::FAULT_KEY)?.iter().values().rev() {
+            let Ok(fault) = rmp_serde::from_slice::<DeviceFault>(&value?) else {
+                continue;
+            };
+            if id.is_some_and(|id| fault.id != id) {
+                continue;
+            }
+            faults.push(fault);
+        }
+        Ok(faults)
     }
 
-    Ok(())
-}
+    pub fn get_groups(&self) -> Vec<DeviceGroup> {
+        self.db
+            .scan_prefix(format!("{}:", Self::GROUP_KEY))
+            .values()
+            .filter_map(|i| i.ok())
+            .filter_map(|i| rmp_serde::from_slice(&i).ok())
+            .collect()
+    }
 
-#[derive(Debug, Clone)]
-pub struct DBState {
-    db: sled::Db,
-}
+    pub fn get_group(&self, id: &str) -> Option<DeviceGroup> {
+        self.db
+            .get(format!("{}:{}", Self::GROUP_KEY, id))
+            .ok()
+            .flatten()
+            .as_deref()
+            .and_then(|i| rmp_serde::from_slice(i).ok())
+    }
 
-impl DBState {
-    pub const DEVICE_KEY: &'static str = "devices";
-    pub const CONFIG_KEY: &'static str = "config";
+    pub fn set_group(&self, group: &DeviceGroup) -> Result<(), DBError> {
+        let data = rmp_serde::to_vec(group)?;
+        self.db
+            .insert(format!("{}:{}", Self::GROUP_KEY, group.id), data)?;
+        Ok(())
+    }
 
-    pub fn new(db: sled::Db) -> DBState {
-        DBState { db }
+    /// Returns whether there was a group to remove
+    pub fn delete_group(&self, id: &str) -> Result<bool, DBError> {
+        let removed = self.db.remove(format!("{}:{}", Self::GROUP_KEY, id))?;
+        Ok(removed.is_some())
     }
-    pub fn flush(&self) -> Result<usize, sled::Error> {
-        self.db.flush()
+
+    pub fn get_schedule_rules(&self) -> Vec<ScheduleRule> {
+        self.db
+            .scan_prefix(format!("{}:", Self::SCHEDULE_KEY))
+            .values()
+            .filter_map(|i| i.ok())
+            .filter_map(|i| rmp_serde::from_slice(&i).ok())
+            .collect()
     }
-    pub fn get_cached_name(&self, id: &str) -> Option<String> {
+
+    pub fn get_schedule_rule(&self, id: &str) -> Option<ScheduleRule> {
         self.db
-            .get(format!("{}:{}", Self::DEVICE_KEY, id))
+            .get(format!("{}:{}", Self::SCHEDULE_KEY, id))
             .ok()
             .flatten()
             .as_deref()
-            .map(String::from_utf8_lossy)
-            .map(|i| i.to_string())
+            .and_then(|i| rmp_serde::from_slice(i).ok())
     }
 
-    pub fn set_cached_name(&self, id: &str, name: &str) {
+    pub fn set_schedule_rule(&self, rule: &ScheduleRule) -> Result<(), DBError> {
+        let data = rmp_serde::to_vec(rule)?;
         self.db
-            .insert(format!("{}:{}", Self::DEVICE_KEY, id), name)
-            .unwrap();
+            .insert(format!("{}:{}", Self::SCHEDULE_KEY, rule.id), data)?;
+        Ok(())
     }
 
-    pub fn get_config(&self) -> Option<UserPreferences> {
+    /// Removes the rule and its last run. Returns whether there was a rule to remove
+    pub fn delete_schedule_rule(&self, id: &str) -> Result<bool, DBError> {
+        let removed = self.db.remove(format!("{}:{}", Self::SCHEDULE_KEY, id))?;
         self.db
-            .get(Self::CONFIG_KEY)
+            .remove(format!("{}:{}", Self::SCHEDULE_RUN_KEY, id))?;
+        Ok(removed.is_some())
+    }
+
+    pub fn get_last_run(&self, id: &str) -> Option<RuleRun> {
+        self.db
+            .get(format!("{}:{}", Self::SCHEDULE_RUN_KEY, id))
             .ok()
             .flatten()
             .as_deref()
             .and_then(|i| rmp_serde::from_slice(i).ok())
     }
-    pub fn set_config(&self, config: &UserPreferences) {
-        println!("Setting config: {:#?}", config);
-        let data = rmp_serde::to_vec(config).unwrap();
-        self.db.insert(Self::CONFIG_KEY, data).unwrap();
+
+    pub fn set_last_run(&self, run: &RuleRun) -> Result<(), DBError> {
+        let data = rmp_serde::to_vec(run)?;
+        self.db
+            .insert(format!("{}:{}", Self::SCHEDULE_RUN_KEY, run.rule), data)?;
+        Ok(())
+    }
+
+    /// Removes everything recorded before the cutoff, for every device. Returns how many samples were removed
+    pub fn prune_history(&self, before: SystemTime) -> Result<usize, DBError> {
+        let prefix = format!("{}:", Self::HISTORY_KEY);
+        let mut removed = 0;
+        for name in self.db.tree_names() {
+            if !name.starts_with(prefix.as_bytes()) {
+                continue;
+            }
+            let tree = self.db.open_tree(name)?;
+            for key in tree.range(..Self::history_key(before)).keys() {
+                tree.remove(key?)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
     }
 }
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 #[typeshare]
-enum TemperatureUnit {
+pub enum TemperatureUnit {
     Fahrenheit,
     Celsius,
 }
 
+impl TemperatureUnit {
+    pub fn from_celsius(&self, celsius: f32) -> f32 {
+        match self {
+            TemperatureUnit::Fahrenheit => celsius * 9.0 / 5.0 + 32.0,
+            TemperatureUnit::Celsius => celsius,
+        }
+    }
+}
+
+/// Which remembered devices to connect to on startup
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[typeshare]
+pub enum AutoconnectPreference {
+    All,
+    /// Only the device that was connected to most recently
+    LastUsed,
+    #[default]
+    None,
+}
+
+/// Reads the autoconnect preference from configs saved before there was a choice of devices,
+/// when it was the `autoconnect_last_device` flag
+fn deserialize_autoconnect<'de, D>(deserializer: D) -> Result<AutoconnectPreference, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Stored {
+        Preference(AutoconnectPreference),
+        LastDevice(bool),
+    }
+
+    Ok(match Stored::deserialize(deserializer)? {
+        Stored::Preference(preference) => preference,
+        Stored::LastDevice(true) => AutoconnectPreference::LastUsed,
+        Stored::LastDevice(false) => AutoconnectPreference::None,
+    })
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 #[typeshare]
 pub struct UserPreferences {
     adapter: String,
     unit: TemperatureUnit,
-    autoconnect_last_device: bool,
+    #[serde(default, deserialize_with = "deserialize_autoconnect")]
+    autoconnect: AutoconnectPreference,
+    /// Which mode to use for each range of target temperatures
+    #[serde(default)]
+    temp_bands: TempBands,
+    /// How many days of status history to keep. Zero keeps all of it
+    #[serde(default = "UserPreferences::default_history_retention_days")]
+    history_retention_days: u32,
+}
+
+impl UserPreferences {
+    fn default_history_retention_days() -> u32 {
+        30
+    }
+
+    pub fn unit(&self) -> TemperatureUnit {
+        self.unit
+    }
+
+    pub fn temp_bands(&self) -> &TempBands {
+        &self.temp_bands
+    }
+
+    /// How long to keep the status history for, or `None` to keep it forever
+    pub fn history_retention(&self) -> Option<Duration> {
+        match self.history_retention_days {
+            0 => None,
+            days => Some(Duration::from_secs(days as u64 * 24 * 60 * 60)),
+        }
+    }
 }
 
 impl Default for UserPreferences {
@@ -263,7 +1326,9 @@ impl Default for UserPreferences {
         Self {
             adapter: String::new(),
             unit: TemperatureUnit::Fahrenheit,
-            autoconnect_last_device: false,
+            autoconnect: AutoconnectPreference::None,
+            temp_bands: TempBands::default(),
+            history_retention_days: Self::default_history_retention_days(),
         }
     }
 }
@@ -344,13 +1409,23 @@ where
 /// The primary interface for interacting with the device.
 pub struct BedJet {
     pub id: String,
-    peripheral: Peripheral,
+    peripheral: Arc<dyn Transport>,
     device_status: Characteristic,
     friendly_name: Characteristic,
     command: Characteristic,
     extended_data: Characteristic,
+    /// Only present on devices with a Wi-Fi radio
+    wifi_ssid: Option<Characteristic>,
+    wifi_password: Option<Characteristic>,
     device_status_send: Arc<watch::Sender<Option<DeviceStatus>>>,
+    fault_send: broadcast::Sender<DeviceFault>,
     notification_task: Arc<Mutex<Option<JoinHandle<Result<(), DeviceError>>>>>,
+    parameter_lock: Arc<Mutex<()>>,
+    /// Holds the time of the last write to the command characteristic.
+    /// Writers take turns with it in the order they asked, since the lock is fair.
+    command_queue: Arc<Mutex<Option<Instant>>>,
+    /// The newest ticket handed out for each kind of set-point, so older ones that are still queued can be dropped
+    set_point_tickets: Arc<std::sync::Mutex<HashMap<CommandClass, u64>>>,
 }
 
 impl BedJet {
@@ -362,8 +1437,24 @@ impl BedJet {
     pub const COMMANDS_UUID: Uuid = Uuid::from_u128(649413073577720503353409796728180);
     pub const EXTENDED_DATA_UUID: Uuid = Uuid::from_u128(649492301740234767691003340678516);
 
+    /// How many faults can wait for the fault log before the oldest are dropped
+    const FAULT_CAPACITY: usize = 16;
+    /// How long to wait for the device to answer a parameter request
+    const PARAMETER_TIMEOUT: Duration = Duration::from_secs(2);
+    const PARAMETER_POLL_INTERVAL: Duration = Duration::from_millis(50);
+    /// How long to wait for the first status from a device that hasn't sent one yet
+    const STATUS_TIMEOUT: Duration = Duration::from_secs(2);
+    /// How long the device gets to join the network and report the result of the connection test
+    const WIFI_TEST_TIMEOUT: Duration = Duration::from_secs(90);
+    /// How long the device gets to download and install a firmware update
+    const FIRMWARE_UPDATE_TIMEOUT: Duration = Duration::from_secs(600);
+    /// How long a single reconnect attempt can take before it's abandoned
+    const RECONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+    /// The shortest time between writes to the command characteristic. Writes that come in faster than this get dropped by the device
+    const COMMAND_GAP: Duration = Duration::from_millis(100);
+
     pub async fn new(
-        peripheral: Peripheral,
+        peripheral: impl Transport + 'static,
         handle: Option<AppHandle>,
     ) -> Result<Self, DeviceError> {
         peripheral.connect().await?;
@@ -376,9 +1467,10 @@ impl BedJet {
             .collect();
 
         let (device_status_send, _) = watch::channel(None);
+        let (fault_send, _) = broadcast::channel(Self::FAULT_CAPACITY);
         let val = Self {
-            id: peripheral.id().to_string(),
-            peripheral,
+            id: peripheral.id(),
+            peripheral: Arc::new(peripheral),
             device_status: map
                 .remove(&Self::DEVICE_STATUS_UUID)
                 .ok_or(DeviceError::MissingCharacteristic)?,
@@ -391,8 +1483,14 @@ impl BedJet {
             extended_data: map
                 .remove(&Self::EXTENDED_DATA_UUID)
                 .ok_or(DeviceError::MissingCharacteristic)?,
+            wifi_ssid: map.remove(&Self::WIFI_SSID_UUID),
+            wifi_password: map.remove(&Self::WIFI_PASSWORD_UUID),
             device_status_send: Arc::new(device_status_send),
+            fault_send,
             notification_task: Arc::new(Mutex::new(None)),
+            parameter_lock: Arc::new(Mutex::new(())),
+            command_queue: Arc::new(Mutex::new(None)),
+            set_point_tickets: Arc::new(std::sync::Mutex::new(HashMap::new())),
         };
 
         val.connect(handle).await?;
@@ -421,11 +1519,78 @@ impl BedJet {
         Ok(())
     }
 
+    /// Stops handling notifications, for a device that's dropped without disconnecting the peripheral
+    pub async fn stop_notifications(&self) {
+        if let Some(task) = self.notification_task.lock().await.take() {
+            task.abort();
+        }
+    }
+
     pub async fn disconnect(&self) -> Result<(), DeviceError> {
         self.peripheral.disconnect().await?;
         Ok(())
     }
 
+    /// Brings back a dropped connection.
+    /// The device forgets its subscriptions when it drops, so this restarts the notification handler and listens to the status again.
+    pub async fn resume(&self, handle: Option<AppHandle>) -> Result<(), DeviceError> {
+        self.peripheral.connect().await?;
+        self.peripheral.discover_services().await?;
+
+        let mut task = self.notification_task.lock().await;
+        if let Some(task) = task.take() {
+            task.abort();
+        }
+        let inner = self.clone();
+        task.replace(tokio::task::spawn(async move {
+            inner.handle_notifications(handle).await
+        }));
+        drop(task);
+
+        self.listen_status().await?;
+        Ok(())
+    }
+
+    /// Keeps trying to [`BedJet::resume`] the connection, backing off between attempts as set by the policy
+    pub async fn reconnect(
+        &self,
+        policy: ReconnectPolicy,
+        handle: Option<&AppHandle>,
+    ) -> Result<(), DeviceError> {
+        let emit = |event: DeviceEvent| {
+            if let Some(handle) = handle {
+                let _ = handle.emit("DeviceEvent", event);
+            }
+        };
+        let event = |attempt| ReconnectEvent {
+            id: self.id.clone(),
+            attempt,
+        };
+
+        for attempt in 1..=policy.max_attempts {
+            emit(DeviceEvent::Reconnecting(event(attempt)));
+            tokio::time::sleep(policy.delay(attempt)).await;
+
+            let result =
+                tokio::time::timeout(Self::RECONNECT_TIMEOUT, self.resume(handle.cloned()))
+                    .await
+                    .unwrap_or(Err(DeviceError::Timeout));
+            match result {
+                Ok(()) => {
+                    emit(DeviceEvent::Reconnected(event(attempt)));
+                    return Ok(());
+                }
+                Err(err) => println!(
+                    "Reconnect attempt {} to {} failed: {}",
+                    attempt, self.id, err
+                ),
+            }
+        }
+
+        emit(DeviceEvent::GaveUp(event(policy.max_attempts)));
+        Err(DeviceError::ReconnectFailed)
+    }
+
     async fn handle_notifications(&self, handle: Option<AppHandle>) -> Result<(), DeviceError> {
         let mut stream = self.peripheral.notifications().await?;
         while let Some(msg) = stream.next().await {
@@ -452,6 +1617,16 @@ impl BedJet {
         self.device_status_send.subscribe()
     }
 
+    /// Gets each fault once, when the device goes from running normally to reporting it
+    pub fn subscribe_faults(&self) -> broadcast::Receiver<DeviceFault> {
+        self.fault_send.subscribe()
+    }
+
+    /// The last status the device reported, without waiting for one
+    pub fn current_status(&self) -> Option<DeviceStatus> {
+        *self.device_status_send.borrow()
+    }
+
     pub async fn get_status(&self) -> Result<DeviceStatus, DeviceError> {
         let mut recv = self.device_status_send.subscribe();
 
@@ -496,6 +1671,19 @@ impl BedJet {
             }
         }
 
+        let was_faulted = prev.is_some_and(|i| i.shutdown_code.is_fault());
+        if !was_faulted && status.shutdown_code.is_fault() {
+            let fault = DeviceFault {
+                id: self.id.clone(),
+                code: status.shutdown_code,
+                time: SystemTime::now(),
+            };
+            if let Some(handle) = handle {
+                let _ = handle.emit("DeviceFault", fault.clone());
+            }
+            let _ = self.fault_send.send(fault);
+        }
+
         Ok(())
     }
 
@@ -503,12 +1691,437 @@ impl BedJet {
         let data = self.peripheral.read(&self.friendly_name).await?;
         Ok(String::from_utf8_lossy(&data).to_string())
     }
+    /// Queues the command up behind every command sent before it, and resolves once it's been written.
+    ///
+    /// Set-points like the target temperature are merged: if a newer one of the same kind is queued up
+    /// before this one gets its turn, this one is dropped and resolves straight away.
     pub async fn send_command(&self, command: Command) -> Result<(), DeviceError> {
         let data = command.encode()?;
-        self.peripheral
-            .write(&self.command, &data, WriteType::WithoutResponse)
+        let ticket = command.set_point_class().map(|class| {
+            let mut tickets = self
+                .set_point_tickets
+                .lock()
+                .expect("Set-point tickets were poisoned");
+            let ticket = tickets.entry(class).or_default();
+            *ticket += 1;
+            (class, *ticket)
+        });
+
+        let mut last_write = self.command_queue.lock().await;
+        Self::wait_for_gap(*last_write).await;
+        // Anything queued up while waiting can still replace this one
+        if let Some((class, ticket)) = ticket {
+            let tickets = self
+                .set_point_tickets
+                .lock()
+                .expect("Set-point tickets were poisoned");
+            if tickets.get(&class) != Some(&ticket) {
+                return Ok(());
+            }
+        }
+
+        self.write_now(&mut last_write, &data, WriteType::WithoutResponse)
+            .await
+    }
+
+    /// Writes to the command characteristic once it's this write's turn
+    async fn write_command(&self, data: &[u8], write_type: WriteType) -> Result<(), DeviceError> {
+        let mut last_write = self.command_queue.lock().await;
+        Self::wait_for_gap(*last_write).await;
+        self.write_now(&mut last_write, data, write_type).await
+    }
+
+    /// Writes to the command characteristic, for callers that already have their turn in the queue
+    async fn write_now(
+        &self,
+        last_write: &mut Option<Instant>,
+        data: &[u8],
+        write_type: WriteType,
+    ) -> Result<(), DeviceError> {
+        let result = self.peripheral.write(&self.command, data, write_type).await;
+        *last_write = Some(Instant::now());
+        Ok(result?)
+    }
+
+    async fn wait_for_gap(last_write: Option<Instant>) {
+        if let Some(last_write) = last_write {
+            tokio::time::sleep(Self::COMMAND_GAP.saturating_sub(last_write.elapsed())).await;
+        }
+    }
+
+    /// Sets the target temperature, switching to the mode whose band covers it.
+    ///
+    /// Every command needed for that is sent in one turn of the queue, so nothing else can be sent in the middle of it.
+    pub async fn set_target_temperature(
+        &self,
+        temp: TempParam,
+        bands: &TempBands,
+    ) -> Result<(), DeviceError> {
+        // Waited for before taking the queue, so a device that hasn't reported yet doesn't hold up other commands
+        let status = tokio::time::timeout(Self::STATUS_TIMEOUT, self.get_status())
+            .await
+            .map_err(|_| DeviceError::Timeout)??;
+        bands.validate_for(&status)?;
+        let commands = bands.commands(&status, temp)?;
+
+        let mut last_write = self.command_queue.lock().await;
+        for command in commands {
+            let data = command.encode()?;
+            Self::wait_for_gap(*last_write).await;
+            self.write_now(&mut last_write, &data, WriteType::WithoutResponse)
+                .await?;
+        }
+        Ok(())
+    }
+
+    /// Sends the command until the status shows the expected change, and returns that status.
+    /// The command can be sent more than once, so it shouldn't be one that's relative to the current state like [`ButtonCode::FanUp`].
+    pub async fn send_command_confirmed(
+        &self,
+        command: Command,
+        expected: ExpectedStatus,
+        policy: ConfirmationPolicy,
+    ) -> Result<DeviceStatus, DeviceError> {
+        let mut recv = self.device_status_send.subscribe();
+        for _ in 0..policy.attempts {
+            self.send_command(command.clone()).await?;
+
+            let matched = recv.wait_for(|i| i.as_ref().is_some_and(|i| expected.matches(i)));
+            if let Ok(status) = tokio::time::timeout(policy.timeout, matched).await {
+                let status = status?
+                    .to_owned()
+                    .expect("Value was checked as Some, and was actually None. This is impossible");
+                return Ok(status);
+            }
+        }
+
+        Err(DeviceError::NotAcknowledged {
+            attempts: policy.attempts,
+        })
+    }
+
+    pub async fn apply_settings(&self, settings: &DeviceSettings) -> Result<(), DeviceError> {
+        for button in settings.buttons() {
+            self.send_command(Command::Button(button)).await?;
+        }
+        Ok(())
+    }
+
+    /// Requests a parameter from the device, and reads back the value it places in the extended data characteristic.
+    ///
+    /// The value is returned with the length the device reported, which can be shorter than [`ParameterCode::LENGTH`]
+    pub async fn read_parameter(&self, code: ParameterCode) -> Result<Vec<u8>, DeviceError> {
+        // The extended data characteristic only holds one response at a time
+        let _guard = self.parameter_lock.lock().await;
+        let request = GetParameter(code).encode()?;
+
+        let response = async {
+            self.write_command(&request, WriteType::WithResponse)
+                .await?;
+
+            loop {
+                let data = self.peripheral.read(&self.extended_data).await?;
+                match ParameterResponse::read_from(Cursor::new(data)) {
+                    Ok(response) if response.code == code => return Ok(response.value),
+                    // The device hasn't replaced the previous response yet
+                    _ => tokio::time::sleep(Self::PARAMETER_POLL_INTERVAL).await,
+                }
+            }
+        };
+
+        tokio::time::timeout(Self::PARAMETER_TIMEOUT, response)
+            .await
+            .map_err(|_| DeviceError::Timeout)?
+    }
+
+    /// Writes the value of a parameter, zero padding it to [`ParameterCode::LENGTH`]
+    pub async fn write_parameter(
+        &self,
+        code: ParameterCode,
+        value: &[u8],
+    ) -> Result<(), DeviceError> {
+        let _guard = self.parameter_lock.lock().await;
+        let request = SetParameter { code, value }.encode()?;
+
+        tokio::time::timeout(
+            Self::PARAMETER_TIMEOUT,
+            self.write_command(&request, WriteType::WithResponse),
+        )
+        .await
+        .map_err(|_| DeviceError::Timeout)??;
+
+        Ok(())
+    }
+
+    async fn read_name_parameter(&self, code: ParameterCode) -> Result<String, DeviceError> {
+        let value = self.read_parameter(code).await?;
+        Ok(read_name(Cursor::new(value))?)
+    }
+
+    async fn write_name_parameter(
+        &self,
+        code: ParameterCode,
+        name: &str,
+    ) -> Result<(), DeviceError> {
+        self.write_parameter(code, &encode_name(name)?).await
+    }
+
+    pub async fn get_device_name(&self) -> Result<String, DeviceError> {
+        self.read_name_parameter(ParameterCode::DeviceName).await
+    }
+
+    pub async fn set_device_name(&self, name: &str) -> Result<(), DeviceError> {
+        self.write_name_parameter(ParameterCode::DeviceName, name)
+            .await
+    }
+
+    pub async fn get_firmware_version(&self) -> Result<FirmwareVersion, DeviceError> {
+        let data = self
+            .read_parameter(ParameterCode::FirmwareVersionCodes)
+            .await?;
+        Ok(FirmwareVersion::read_from(Cursor::new(data))?)
+    }
+
+    pub async fn get_device_info(&self) -> Result<DeviceInfo, DeviceError> {
+        Ok(DeviceInfo {
+            id: self.id.clone(),
+            name: self.get_friendly_name().await?,
+            firmware: self.get_firmware_version().await?,
+        })
+    }
+
+    pub async fn get_biorhythm(&self, slot: u8) -> Result<BiorhythmProgram, DeviceError> {
+        let name = self
+            .read_name_parameter(ParameterCode::biorhythm_name(slot)?)
             .await?;
 
+        let mut data = Vec::new();
+        for fragment in 1..=Biorhythm::FRAGMENTS as u8 {
+            let code = ParameterCode::biorhythm_fragment(slot, fragment)?;
+            let mut value = self.read_parameter(code).await?;
+            value.resize(ParameterCode::LENGTH as usize, 0);
+            data.append(&mut value);
+        }
+        let biorhythm = Biorhythm::read_from(Cursor::new(data))?;
+
+        Ok(BiorhythmProgram {
+            slot,
+            name,
+            biorhythm,
+        })
+    }
+
+    pub async fn set_biorhythm(&self, program: &BiorhythmProgram) -> Result<(), DeviceError> {
+        // Encode everything up front so an invalid program doesn't get partially written
+        let name_code = ParameterCode::biorhythm_name(program.slot)?;
+        let name = encode_name(&program.name)?;
+        let fragments = program.biorhythm.fragments()?;
+
+        self.write_parameter(name_code, &name).await?;
+        for (fragment, data) in (1..).zip(fragments) {
+            let code = ParameterCode::biorhythm_fragment(program.slot, fragment)?;
+            self.write_parameter(code, &data).await?;
+        }
+
         Ok(())
     }
+
+    pub async fn get_memory_preset(&self, slot: u8) -> Result<MemoryPreset, DeviceError> {
+        let name = self
+            .read_name_parameter(ParameterCode::memory_name(slot)?)
+            .await?;
+
+        Ok(MemoryPreset { slot, name })
+    }
+
+    /// Stores the current mode, temperature, fan step and runtime in the slot
+    pub async fn store_memory_preset(&self, slot: u8) -> Result<(), DeviceError> {
+        self.send_command(Command::Button(ButtonCode::memory_store(slot)?))
+            .await
+    }
+
+    pub async fn rename_memory_preset(&self, slot: u8, name: String) -> Result<(), DeviceError> {
+        self.write_name_parameter(ParameterCode::memory_name(slot)?, &name)
+            .await
+    }
+
+    pub async fn recall_memory_preset(&self, slot: u8) -> Result<(), DeviceError> {
+        self.send_command(Command::Button(ButtonCode::memory_recall(slot)?))
+            .await
+    }
+
+    /// Writes the Wi-Fi credentials to the device, and then runs its connection test.
+    ///
+    /// Progress is emitted as a [`WifiProvisioningEvent`] when a handle is provided.
+    pub async fn provision_wifi(
+        &self,
+        ssid: &str,
+        password: &str,
+        handle: Option<&AppHandle>,
+    ) -> Result<(), DeviceError> {
+        if ssid.is_empty() || ssid.len() > 32 {
+            return Err(InterfaceError::InvalidParameter.into());
+        }
+        // Open networks have no password, otherwise it's a WPA passphrase or 64 character hex key
+        if !password.is_empty() && !(8..=64).contains(&password.len()) {
+            return Err(InterfaceError::InvalidParameter.into());
+        }
+
+        let (Some(wifi_ssid), Some(wifi_password)) = (&self.wifi_ssid, &self.wifi_password) else {
+            return Err(DeviceError::MissingCharacteristic);
+        };
+
+        let emit = |progress: WifiProvisioningProgress| {
+            if let Some(handle) = handle {
+                let event = WifiProvisioningEvent {
+                    id: self.id.clone(),
+                    progress,
+                };
+                let _ = handle.emit("WifiProvisioningEvent", event);
+            }
+        };
+
+        self.peripheral
+            .write(wifi_ssid, ssid.as_bytes(), WriteType::WithResponse)
+            .await?;
+        self.peripheral
+            .write(wifi_password, password.as_bytes(), WriteType::WithResponse)
+            .await?;
+        self.send_command(Command::Button(ButtonCode::SetConfigCompleteFlag))
+            .await?;
+        emit(WifiProvisioningProgress::CredentialsWritten);
+
+        // Subscribe before starting the test so none of the transitions get missed
+        let mut recv = self.subscribe_status();
+        recv.mark_unchanged();
+        let before = recv.borrow().map(|i| i.update_status);
+        self.send_command(Command::Button(ButtonCode::StartConnectionTest))
+            .await?;
+
+        let mut last_failure = None;
+        let result = self
+            .follow_update_status(
+                recv,
+                before,
+                Self::WIFI_TEST_TIMEOUT,
+                |status| match status {
+                    UpdateStatus::ConnectionTestOK => {
+                        emit(WifiProvisioningProgress::Succeeded);
+                        Some(Ok(()))
+                    }
+                    UpdateStatus::ConnectionTestFailed => {
+                        let reason = last_failure.unwrap_or(status);
+                        emit(WifiProvisioningProgress::Failed(reason));
+                        Some(Err(DeviceError::WifiTestFailed(reason)))
+                    }
+                    status => {
+                        if status.is_failure() {
+                            last_failure = Some(status);
+                        }
+                        emit(WifiProvisioningProgress::Testing(status));
+                        None
+                    }
+                },
+            )
+            .await;
+
+        if let Err(DeviceError::Timeout) = result {
+            emit(WifiProvisioningProgress::TimedOut);
+        }
+        result
+    }
+
+    /// Asks the device to check for and install a firmware update over Wi-Fi.
+    ///
+    /// Progress is emitted as a [`FirmwareUpdateEvent`] when a handle is provided.
+    pub async fn update_firmware(
+        &self,
+        handle: Option<&AppHandle>,
+    ) -> Result<FirmwareUpdateOutcome, DeviceError> {
+        let emit = |progress: FirmwareUpdateProgress| {
+            if let Some(handle) = handle {
+                let event = FirmwareUpdateEvent {
+                    id: self.id.clone(),
+                    progress,
+                };
+                let _ = handle.emit("FirmwareUpdateEvent", event);
+            }
+        };
+
+        let mut recv = self.subscribe_status();
+        recv.mark_unchanged();
+        let before = recv.borrow().map(|i| i.update_status);
+        self.send_command(Command::Button(ButtonCode::StartFirmwareUpdate))
+            .await?;
+
+        let mut last_progress = None;
+        let result = self
+            .follow_update_status(recv, before, Self::FIRMWARE_UPDATE_TIMEOUT, |status| {
+                let (progress, result) = match status {
+                    UpdateStatus::NoUpdateNeeded => (
+                        FirmwareUpdateProgress::UpToDate,
+                        Some(Ok(FirmwareUpdateOutcome::UpToDate)),
+                    ),
+                    // The device drops off once it restarts, so this is the last status we'll see
+                    UpdateStatus::RestartingBedJet | UpdateStatus::RestartingBedJetTerminal => (
+                        FirmwareUpdateProgress::Restarting,
+                        Some(Ok(FirmwareUpdateOutcome::Updated)),
+                    ),
+                    status if status.is_failure() => (
+                        FirmwareUpdateProgress::Failed(status),
+                        Some(Err(DeviceError::FirmwareUpdateFailed(status))),
+                    ),
+                    UpdateStatus::CheckingForUpdate => (FirmwareUpdateProgress::Checking, None),
+                    UpdateStatus::Updating => (FirmwareUpdateProgress::Updating, None),
+                    _ => (FirmwareUpdateProgress::Connecting, None),
+                };
+
+                if last_progress.as_ref() != Some(&progress) {
+                    emit(progress.clone());
+                    last_progress = Some(progress);
+                }
+                result
+            })
+            .await;
+
+        match &result {
+            Ok(FirmwareUpdateOutcome::Updated) => emit(FirmwareUpdateProgress::Done),
+            Err(DeviceError::Timeout) => emit(FirmwareUpdateProgress::TimedOut),
+            _ => {}
+        }
+        result
+    }
+
+    /// Passes each new update status the device reports to `on_status`, until it returns a result or the timeout elapses.
+    /// `before` is the status from before the test or update was started. It's left over from the last one,
+    /// so it's skipped until the device reports something else
+    async fn follow_update_status<T>(
+        &self,
+        mut recv: watch::Receiver<Option<DeviceStatus>>,
+        before: Option<UpdateStatus>,
+        timeout: Duration,
+        mut on_status: impl FnMut(UpdateStatus) -> Option<Result<T, DeviceError>>,
+    ) -> Result<T, DeviceError> {
+        let follow = async {
+            let mut last_status = before;
+            loop {
+                recv.changed().await?;
+                let Some(status) = recv.borrow_and_update().map(|i| i.update_status) else {
+                    continue;
+                };
+                if last_status == Some(status) {
+                    continue;
+                }
+                last_status = Some(status);
+
+                if let Some(result) = on_status(status) {
+                    return result;
+                }
+            }
+        };
+
+        tokio::time::timeout(timeout, follow)
+            .await
+            .map_err(|_| DeviceError::Timeout)?
+    }
 }
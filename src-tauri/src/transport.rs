@@ -0,0 +1,87 @@
+use async_trait::async_trait;
+use btleplug::{
+    api::{Characteristic, Peripheral as PeripheralTrait, ValueNotification, WriteType},
+    platform::Peripheral,
+};
+use futures::Stream;
+use std::{collections::BTreeSet, fmt::Debug, pin::Pin};
+
+pub type NotificationStream = Pin<Box<dyn Stream<Item = ValueNotification> + Send>>;
+
+/// The subset of a BLE peripheral that a [`crate::state::BedJet`] needs to talk to the device.
+///
+/// This lets the device logic run against something other than a real peripheral, like the
+/// simulated BedJet behind the `simulator` feature.
+#[async_trait]
+pub trait Transport: Debug + Send + Sync {
+    fn id(&self) -> String;
+    async fn is_connected(&self) -> Result<bool, btleplug::Error>;
+    async fn connect(&self) -> Result<(), btleplug::Error>;
+    async fn disconnect(&self) -> Result<(), btleplug::Error>;
+    async fn discover_services(&self) -> Result<(), btleplug::Error>;
+    /// Only populated after [`Transport::discover_services`] has been called
+    fn characteristics(&self) -> BTreeSet<Characteristic>;
+    async fn read(&self, characteristic: &Characteristic) -> Result<Vec<u8>, btleplug::Error>;
+    async fn write(
+        &self,
+        characteristic: &Characteristic,
+        data: &[u8],
+        write_type: WriteType,
+    ) -> Result<(), btleplug::Error>;
+    async fn subscribe(&self, characteristic: &Characteristic) -> Result<(), btleplug::Error>;
+    async fn unsubscribe(&self, characteristic: &Characteristic) -> Result<(), btleplug::Error>;
+    /// A stream of the notifications for every subscribed characteristic
+    async fn notifications(&self) -> Result<NotificationStream, btleplug::Error>;
+}
+
+#[async_trait]
+impl Transport for Peripheral {
+    fn id(&self) -> String {
+        PeripheralTrait::id(self).to_string()
+    }
+
+    async fn is_connected(&self) -> Result<bool, btleplug::Error> {
+        PeripheralTrait::is_connected(self).await
+    }
+
+    async fn connect(&self) -> Result<(), btleplug::Error> {
+        PeripheralTrait::connect(self).await
+    }
+
+    async fn disconnect(&self) -> Result<(), btleplug::Error> {
+        PeripheralTrait::disconnect(self).await
+    }
+
+    async fn discover_services(&self) -> Result<(), btleplug::Error> {
+        PeripheralTrait::discover_services(self).await
+    }
+
+    fn characteristics(&self) -> BTreeSet<Characteristic> {
+        PeripheralTrait::characteristics(self)
+    }
+
+    async fn read(&self, characteristic: &Characteristic) -> Result<Vec<u8>, btleplug::Error> {
+        PeripheralTrait::read(self, characteristic).await
+    }
+
+    async fn write(
+        &self,
+        characteristic: &Characteristic,
+        data: &[u8],
+        write_type: WriteType,
+    ) -> Result<(), btleplug::Error> {
+        PeripheralTrait::write(self, characteristic, data, write_type).await
+    }
+
+    async fn subscribe(&self, characteristic: &Characteristic) -> Result<(), btleplug::Error> {
+        PeripheralTrait::subscribe(self, characteristic).await
+    }
+
+    async fn unsubscribe(&self, characteristic: &Characteristic) -> Result<(), btleplug::Error> {
+        PeripheralTrait::unsubscribe(self, characteristic).await
+    }
+
+    async fn notifications(&self) -> Result<NotificationStream, btleplug::Error> {
+        PeripheralTrait::notifications(self).await
+    }
+}
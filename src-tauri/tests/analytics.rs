@@ -0,0 +1,108 @@
+use std::{
+    io::Cursor,
+    time::{Duration, SystemTime},
+};
+
+use app_lib::{
+    analytics::{ModeDuration, Night, NightSummary, ShutdownEvent, TargetDeviation, TempStats},
+    proto::{Decode, DeviceStatus, OperatingMode, ShutDownCode},
+};
+
+fn at(minutes: u64) -> SystemTime {
+    SystemTime::UNIX_EPOCH + Duration::from_secs(1_000_000 + minutes * 60)
+}
+
+fn status(mode: OperatingMode, actual: f32, target: f32, fan_step: u8) -> DeviceStatus {
+    let mut status = DeviceStatus::read_from(Cursor::new([0; 27])).unwrap();
+    status.operating_mode = mode;
+    status.actual_temp = (actual * 2.0) as u8;
+    status.target_temp = (target * 2.0) as u8;
+    status.ambient_temp = 40;
+    status.fan_step = fan_step;
+    status
+}
+
+#[test]
+fn summarizes_a_night() {
+    let night = Night {
+        start: at(0),
+        end: at(60),
+    };
+    let mut fault = status(OperatingMode::Standby, 20.0, 20.0, 0);
+    fault.shutdown_code = ShutDownCode::FanFailure;
+    let samples = [
+        // Still in effect when the night starts
+        (
+            at(0) - Duration::from_secs(60),
+            status(OperatingMode::Cool, 22.0, 24.0, 9),
+        ),
+        (at(4), status(OperatingMode::Cool, 24.0, 24.0, 19)),
+        (at(8), status(OperatingMode::ExtendedHeat, 28.0, 30.0, 9)),
+        (at(12), fault),
+        (at(14), fault),
+        // Nothing for a while, so the device must have been disconnected
+        (at(30), status(OperatingMode::Standby, 20.0, 20.0, 0)),
+        (at(34), status(OperatingMode::Standby, 20.0, 20.0, 0)),
+    ];
+
+    let summary = NightSummary::new(night, &samples);
+    let minutes = |i: u64| Duration::from_secs(i * 60);
+    assert_eq!(
+        summary.modes,
+        [
+            ModeDuration {
+                mode: OperatingMode::Cool,
+                duration: minutes(8)
+            },
+            ModeDuration {
+                mode: OperatingMode::ExtendedHeat,
+                duration: minutes(4)
+            },
+            ModeDuration {
+                mode: OperatingMode::Standby,
+                duration: minutes(2 + 5 + 4 + 5)
+            },
+        ]
+    );
+    // 4 minutes each at 50%, 100% and 50%
+    assert_eq!(summary.average_fan, Some(200.0 / 3.0));
+    assert_eq!(
+        summary.target_deviation,
+        Some(TargetDeviation {
+            mean: 4.0 / 3.0,
+            max: 2.0
+        })
+    );
+    assert_eq!(
+        summary.ambient_temp,
+        Some(TempStats {
+            min: 20.0,
+            max: 20.0,
+            mean: 20.0
+        })
+    );
+    let actual = summary.actual_temp.unwrap();
+    assert_eq!((actual.min, actual.max), (20.0, 28.0));
+    assert!((actual.mean - (22.0 + 24.0 + 28.0 + 20.0 * 4.0) / 7.0).abs() < 0.001);
+    // The same fault reported twice is only one event
+    assert_eq!(
+        summary.shutdowns,
+        [ShutdownEvent {
+            time: at(12),
+            code: ShutDownCode::FanFailure
+        }]
+    );
+}
+
+#[test]
+fn empty_nights_have_nothing_to_report() {
+    let night = Night {
+        start: at(0),
+        end: at(60),
+    };
+    let summary = NightSummary::new(night, &[]);
+    assert!(summary.modes.is_empty());
+    assert_eq!(summary.average_fan, None);
+    assert_eq!(summary.actual_temp, None);
+    assert_eq!(summary.target_deviation, None);
+}
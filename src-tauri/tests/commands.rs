@@ -0,0 +1,53 @@
+use app_lib::{
+    commands::CommandError,
+    proto::{InterfaceError, UpdateStatus},
+    state::DeviceError,
+};
+
+#[test]
+fn device_errors_keep_their_message() {
+    let cases = [
+        (
+            DeviceError::DeviceNotFound,
+            CommandError::NotFound(DeviceError::DeviceNotFound.to_string()),
+        ),
+        (
+            DeviceError::Timeout,
+            CommandError::Timeout(DeviceError::Timeout.to_string()),
+        ),
+        (
+            DeviceError::NotAcknowledged { attempts: 3 },
+            CommandError::DeviceFailed(DeviceError::NotAcknowledged { attempts: 3 }.to_string()),
+        ),
+        (
+            DeviceError::MissingCharacteristic,
+            CommandError::MissingCharacteristic(DeviceError::MissingCharacteristic.to_string()),
+        ),
+        (
+            DeviceError::InterfaceError(InterfaceError::InvalidParameter),
+            CommandError::InvalidParameter(InterfaceError::InvalidParameter.to_string()),
+        ),
+        (
+            DeviceError::BluetoothError(btleplug::Error::NotConnected),
+            CommandError::Bluetooth(btleplug::Error::NotConnected.to_string()),
+        ),
+        (
+            DeviceError::BluetoothError(btleplug::Error::DeviceNotFound),
+            CommandError::NotFound(btleplug::Error::DeviceNotFound.to_string()),
+        ),
+    ];
+
+    for (error, expected) in cases {
+        assert_eq!(CommandError::from(error), expected);
+    }
+}
+
+#[test]
+fn serializes_with_a_message() {
+    let error = CommandError::from(DeviceError::WifiTestFailed(UpdateStatus::UnableToConnect));
+    let json = serde_json::to_value(&error).unwrap();
+
+    assert_eq!(json["type"], "DeviceFailed");
+    assert_eq!(json["value"], error.to_string());
+    assert!(!error.to_string().is_empty());
+}
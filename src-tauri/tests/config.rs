@@ -0,0 +1,59 @@
+use app_lib::state::{DBState, TemperatureUnit};
+use serde::Serialize;
+
+/// How the preferences were saved before the autoconnect preference and the later fields were added
+#[derive(Serialize)]
+struct BaselinePreferences {
+    adapter: String,
+    unit: TemperatureUnit,
+    autoconnect_last_device: bool,
+}
+
+fn load(stored: &impl Serialize) -> serde_json::Value {
+    let db = sled::Config::new().temporary(true).open().unwrap();
+    db.insert(DBState::CONFIG_KEY, rmp_serde::to_vec(stored).unwrap())
+        .unwrap();
+    let config = DBState::new(db)
+        .get_config()
+        .expect("The stored config couldn't be read");
+    serde_json::to_value(config).unwrap()
+}
+
+#[test]
+fn reads_configs_saved_before_the_autoconnect_preference() {
+    let config = load(&BaselinePreferences {
+        adapter: "hci1".to_string(),
+        unit: TemperatureUnit::Celsius,
+        autoconnect_last_device: true,
+    });
+    assert_eq!(config["adapter"], "hci1");
+    assert_eq!(config["unit"], "Celsius");
+    assert_eq!(config["autoconnect"], "LastUsed");
+    assert_eq!(config["history_retention_days"], 30);
+
+    let config = load(&BaselinePreferences {
+        adapter: "hci0".to_string(),
+        unit: TemperatureUnit::Fahrenheit,
+        autoconnect_last_device: false,
+    });
+    assert_eq!(config["autoconnect"], "None");
+}
+
+#[test]
+fn saved_configs_read_back_the_same() {
+    let db = DBState::new(sled::Config::new().temporary(true).open().unwrap());
+    let mut config = load(&BaselinePreferences {
+        adapter: "hci1".to_string(),
+        unit: TemperatureUnit::Celsius,
+        autoconnect_last_device: false,
+    });
+    config["autoconnect"] = "All".into();
+    config["history_retention_days"] = 7.into();
+
+    db.set_config(&serde_json::from_value(config.clone()).unwrap())
+        .unwrap();
+    assert_eq!(
+        serde_json::to_value(db.get_config().unwrap()).unwrap(),
+        config
+    );
+}
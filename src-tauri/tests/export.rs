@@ -0,0 +1,68 @@
+use std::{
+    io::Cursor,
+    time::{Duration, SystemTime},
+};
+
+use app_lib::{
+    export::{write_history, ExportFormat},
+    proto::{Decode, DeviceStatus, OperatingMode},
+    state::TemperatureUnit,
+};
+
+fn samples() -> Vec<(SystemTime, DeviceStatus)> {
+    let mut status = DeviceStatus::read_from(Cursor::new([0; 27])).unwrap();
+    status.operating_mode = OperatingMode::Cool;
+    status.actual_temp = 41;
+    status.target_temp = 42;
+    status.ambient_temp = 40;
+    status.fan_step = 9;
+    status.remaining_hours = 1;
+    status.remaining_minutes = 30;
+    let mut later = status;
+    later.actual_temp = 42;
+    vec![
+        (SystemTime::UNIX_EPOCH + Duration::from_secs(100), status),
+        (SystemTime::UNIX_EPOCH + Duration::from_secs(160), later),
+    ]
+}
+
+fn export(format: ExportFormat, unit: TemperatureUnit) -> String {
+    let mut out = Vec::new();
+    let count = write_history(&mut out, samples().into_iter().map(Ok), format, unit).unwrap();
+    assert_eq!(count, 2);
+    String::from_utf8(out).unwrap()
+}
+
+#[test]
+fn exports_csv_in_the_users_unit() {
+    let csv = export(ExportFormat::Csv, TemperatureUnit::Celsius);
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(
+        lines,
+        [
+            "time,unit,remaining_seconds,actual_temp,target_temp,ambient_temp,operating_mode,fan_percent,shutdown_code,update_status",
+            "100,Celsius,5400,20.5,21,20,Cool,50,Normal,Idle",
+            "160,Celsius,5400,21,21,20,Cool,50,Normal,Idle",
+        ]
+    );
+
+    let csv = export(ExportFormat::Csv, TemperatureUnit::Fahrenheit);
+    assert_eq!(
+        csv.lines().nth(1),
+        Some("100,Fahrenheit,5400,68.9,69.8,68,Cool,50,Normal,Idle")
+    );
+}
+
+#[test]
+fn exports_json_lines() {
+    let json = export(ExportFormat::JsonLines, TemperatureUnit::Fahrenheit);
+    let rows: Vec<serde_json::Value> = json
+        .lines()
+        .map(|i| serde_json::from_str(i).unwrap())
+        .collect();
+    assert_eq!(rows.len(), 2);
+    assert_eq!(rows[0]["time"], 100);
+    assert_eq!(rows[0]["unit"], "Fahrenheit");
+    assert_eq!(rows[1]["actual_temp"], 69.8);
+    assert_eq!(rows[1]["operating_mode"], "Cool");
+}
@@ -0,0 +1,345 @@
+use std::io::Cursor;
+
+use app_lib::proto::{
+    BandError, Biorhythm, BiorhythmStep, ButtonCode, Command, Decode, DeviceStatus, Encode,
+    ExpectedStatus, FanParam, InterfaceError, OperatingMode, SetParamKind, ShutDownCode, TempBand,
+    TempBands, TempParam, UpdateStatus,
+};
+use num_traits::FromPrimitive;
+use proptest::{collection::vec, prelude::*, sample::select};
+
+fn all<T: FromPrimitive>() -> Vec<T> {
+    (0..=u8::MAX).filter_map(T::from_u8).collect()
+}
+
+fn name() -> impl Strategy<Value = String> {
+    "[a-zA-Z0-9 ]{0,15}"
+}
+
+/// Every command, in the form that decoding produces
+fn command() -> impl Strategy<Value = Command> {
+    prop_oneof![
+        select(all::<ButtonCode>()).prop_map(Command::Button),
+        (any::<u8>(), any::<u8>()).prop_map(|(hours, minutes)| Command::SetTime { hours, minutes }),
+        any::<u8>().prop_map(|i| Command::SetTemp(TempParam::Celsius(i as f32 / 2.0))),
+        (0..=19u8).prop_map(|i| Command::SetFan(FanParam::Step(i))),
+        (any::<u8>(), any::<u8>())
+            .prop_map(|(hours, minutes)| Command::SetClock { hours, minutes }),
+        set_param().prop_map(Command::SetParam),
+    ]
+}
+
+fn set_param() -> impl Strategy<Value = SetParamKind> {
+    prop_oneof![
+        name().prop_map(SetParamKind::DeviceName),
+        (1..=3u8, name()).prop_map(|(slot, name)| SetParamKind::MemoryName { slot, name }),
+        (1..=3u8, name()).prop_map(|(slot, name)| SetParamKind::BiorhythmName { slot, name }),
+        (1..=3u8, 1..=4u8, vec(any::<u8>(), 16)).prop_map(|(slot, fragment, data)| {
+            SetParamKind::BiorhythmFragment {
+                slot,
+                fragment,
+                data,
+            }
+        }),
+    ]
+}
+
+fn device_status() -> impl Strategy<Value = DeviceStatus> {
+    (
+        any::<[u8; 11]>(),
+        select(all::<OperatingMode>()),
+        select(all::<ShutDownCode>()),
+        select(all::<UpdateStatus>()),
+    )
+        .prop_map(
+            |(bytes, operating_mode, shutdown_code, update_status)| DeviceStatus {
+                remaining_hours: bytes[0],
+                remaining_minutes: bytes[1],
+                remaining_seconds: bytes[2],
+                actual_temp: bytes[3],
+                target_temp: bytes[4],
+                operating_mode,
+                fan_step: bytes[5],
+                max_duration_hours: bytes[6],
+                max_duration_minutes: bytes[7],
+                min_target_temp: bytes[8],
+                max_target_temp: bytes[9],
+                ambient_temp: bytes[10],
+                shutdown_code,
+                update_status,
+            },
+        )
+}
+
+fn biorhythm() -> impl Strategy<Value = Biorhythm> {
+    let step = (
+        select(all::<OperatingMode>()),
+        any::<u8>(),
+        0..=19u8,
+        any::<u8>(),
+        0..=59u8,
+    )
+        .prop_map(
+            |(operating_mode, target_temp, fan_step, hours, minutes)| BiorhythmStep {
+                operating_mode,
+                target_temp,
+                fan_step,
+                hours,
+                minutes,
+            },
+        );
+    vec(step, 0..=Biorhythm::MAX_STEPS).prop_map(|steps| Biorhythm { steps })
+}
+
+proptest! {
+    #[test]
+    fn commands_round_trip(command in command()) {
+        let bytes = command.encode().unwrap();
+        let decoded = Command::read_from(Cursor::new(&bytes)).unwrap();
+        prop_assert_eq!(decoded, command);
+    }
+
+    #[test]
+    fn fahrenheit_and_percent_encode_the_same_after_decoding(
+        fahrenheit in 32.0f32..=160.0,
+        percent in 0..=100u8,
+    ) {
+        for command in [
+            Command::SetTemp(TempParam::Fahrenheit(fahrenheit)),
+            Command::SetFan(FanParam::Percent(percent)),
+        ] {
+            let bytes = command.encode().unwrap();
+            let decoded = Command::read_from(Cursor::new(&bytes)).unwrap();
+            prop_assert_eq!(decoded.encode().unwrap(), bytes);
+        }
+    }
+
+    #[test]
+    fn device_status_round_trips(status in device_status()) {
+        let bytes = status.encode().unwrap();
+        prop_assert_eq!(bytes.len(), 27);
+        prop_assert_eq!(DeviceStatus::read_from(Cursor::new(bytes)).unwrap(), status);
+    }
+
+    #[test]
+    fn biorhythms_round_trip(biorhythm in biorhythm()) {
+        let fragments = biorhythm.fragments().unwrap();
+        prop_assert_eq!(fragments.len(), Biorhythm::FRAGMENTS);
+        let bytes = fragments.concat();
+        prop_assert_eq!(Biorhythm::read_from(Cursor::new(bytes)).unwrap(), biorhythm);
+    }
+}
+
+#[test]
+fn rejects_unknown_commands() {
+    for bytes in [
+        vec![],
+        vec![0xff, 0x00],
+        vec![0x01, 0xff],
+        vec![0x07, 20],
+        vec![0x40, 0x20, 0x10],
+    ] {
+        assert!(Command::read_from(Cursor::new(bytes)).is_err());
+    }
+}
+
+#[test]
+fn encodes_the_wire_format() {
+    let cases = [
+        (Command::Button(ButtonCode::Cool), vec![0x01, 0x02]),
+        (
+            Command::SetTime {
+                hours: 2,
+                minutes: 30,
+            },
+            vec![0x02, 2, 30],
+        ),
+        (Command::SetTemp(TempParam::Celsius(21.5)), vec![0x03, 43]),
+        (Command::SetFan(FanParam::Percent(50)), vec![0x07, 9]),
+    ];
+    for (command, bytes) in cases {
+        assert_eq!(command.encode().unwrap(), bytes);
+    }
+
+    let name = Command::SetParam(SetParamKind::DeviceName("Bed".to_string()))
+        .encode()
+        .unwrap();
+    assert_eq!(&name[..6], &[0x40, 0x00, 0x10, b'B', b'e', b'd']);
+    assert_eq!(name.len(), 19);
+}
+
+#[test]
+fn expected_status_matches_what_the_device_reports() {
+    let mut status = DeviceStatus::read_from(Cursor::new([0; 27])).unwrap();
+    status.target_temp = 41;
+    status.fan_step = 9;
+    status.operating_mode = OperatingMode::Cool;
+    status.remaining_hours = 1;
+    status.remaining_minutes = 59;
+
+    let matching = [
+        Command::SetTemp(TempParam::Celsius(20.5)),
+        Command::SetFan(FanParam::Percent(50)),
+        Command::Button(ButtonCode::Cool),
+        Command::SetTime {
+            hours: 2,
+            minutes: 0,
+        },
+    ];
+    for command in matching {
+        assert!(command.expected_status().unwrap().matches(&status));
+    }
+
+    assert!(!ExpectedStatus::TargetTemp(TempParam::Celsius(21.0)).matches(&status));
+    assert!(!ExpectedStatus::OperatingMode(OperatingMode::Dry).matches(&status));
+    assert!(Command::Button(ButtonCode::FanUp)
+        .expected_status()
+        .is_none());
+}
+
+#[test]
+fn temperature_bands_switch_modes_and_keep_the_fan_and_time() {
+    let mut status = DeviceStatus::read_from(Cursor::new([0; 27])).unwrap();
+    status.operating_mode = OperatingMode::Cool;
+    status.fan_step = 7;
+    status.remaining_hours = 3;
+    status.remaining_minutes = 15;
+    let bands = TempBands::default();
+
+    assert_eq!(
+        bands.commands(&status, TempParam::Celsius(22.0)).unwrap(),
+        [Command::SetTemp(TempParam::Celsius(22.0))]
+    );
+    assert_eq!(
+        bands.commands(&status, TempParam::Celsius(31.0)).unwrap(),
+        [
+            Command::Button(ButtonCode::ExternalHeat),
+            Command::SetTime {
+                hours: 3,
+                minutes: 15
+            },
+            Command::SetFan(FanParam::Step(7)),
+            Command::SetTemp(TempParam::Celsius(31.0)),
+        ]
+    );
+
+    status.operating_mode = OperatingMode::Dry;
+    status.remaining_hours = 0;
+    status.remaining_minutes = 0;
+    assert_eq!(
+        bands
+            .commands(&status, TempParam::Fahrenheit(68.0))
+            .unwrap(),
+        [
+            Command::Button(ButtonCode::Cool),
+            Command::SetFan(FanParam::Step(7)),
+            Command::SetTemp(TempParam::Fahrenheit(68.0)),
+        ]
+    );
+    // The first band wins where two meet
+    assert_eq!(bands.band_for(25.0).unwrap().mode, OperatingMode::Cool);
+
+    // Out of range temperatures are rejected
+    assert!(matches!(
+        bands.commands(&status, TempParam::Fahrenheit(50.0)),
+        Err(InterfaceError::InvalidParameter)
+    ));
+    assert!(matches!(
+        bands.band_for(40.0),
+        Err(InterfaceError::InvalidParameter)
+    ));
+
+    let empty = TempBands { bands: Vec::new() };
+    assert!(empty.commands(&status, TempParam::Celsius(22.0)).is_err());
+}
+
+#[test]
+fn temperature_bands_are_validated_against_the_device() {
+    let mut status = DeviceStatus::read_from(Cursor::new([0; 27])).unwrap();
+    status.min_target_temp = 38;
+    status.max_target_temp = 86;
+    let band = |mode, min, max| TempBand { mode, min, max };
+
+    assert_eq!(TempBands::default().validate_for(&status), Ok(()));
+    let turbo = TempBands {
+        bands: vec![
+            band(OperatingMode::Cool, 19.0, 27.0),
+            band(OperatingMode::TurboHeat, 27.0, 43.0),
+        ],
+    };
+    assert_eq!(turbo.validate_for(&status), Ok(()));
+
+    let overlapping = TempBands {
+        bands: vec![
+            band(OperatingMode::Cool, 19.0, 26.0),
+            band(OperatingMode::Dry, 25.0, 30.0),
+        ],
+    };
+    assert_eq!(
+        overlapping.validate(),
+        Err(BandError::Overlap(OperatingMode::Cool, OperatingMode::Dry))
+    );
+    let gap = TempBands {
+        bands: vec![
+            band(OperatingMode::Cool, 19.0, 24.0),
+            band(OperatingMode::Dry, 25.0, 30.0),
+        ],
+    };
+    assert_eq!(
+        gap.validate(),
+        Err(BandError::Gap(OperatingMode::Cool, OperatingMode::Dry))
+    );
+    let backwards = TempBands {
+        bands: vec![band(OperatingMode::Dry, 30.0, 25.0)],
+    };
+    assert_eq!(
+        backwards.validate(),
+        Err(BandError::InvalidRange(OperatingMode::Dry))
+    );
+    let standby = TempBands {
+        bands: vec![band(OperatingMode::Standby, 19.0, 25.0)],
+    };
+    assert_eq!(
+        standby.validate(),
+        Err(BandError::UnsupportedMode(OperatingMode::Standby))
+    );
+    assert_eq!(
+        TempBands { bands: Vec::new() }.validate(),
+        Err(BandError::Empty)
+    );
+    let two_heat_modes = TempBands {
+        bands: vec![
+            band(OperatingMode::Cool, 19.0, 25.0),
+            band(OperatingMode::NormalHeat, 25.0, 30.0),
+            band(OperatingMode::ExtendedHeat, 30.0, 43.0),
+        ],
+    };
+    assert_eq!(
+        two_heat_modes.validate(),
+        Err(BandError::MultipleHeatModes(
+            OperatingMode::NormalHeat,
+            OperatingMode::ExtendedHeat
+        ))
+    );
+    let cool_twice = TempBands {
+        bands: vec![
+            band(OperatingMode::Cool, 19.0, 22.0),
+            band(OperatingMode::Dry, 22.0, 25.0),
+            band(OperatingMode::Cool, 25.0, 27.0),
+        ],
+    };
+    assert_eq!(
+        cool_twice.validate(),
+        Err(BandError::DuplicateMode(OperatingMode::Cool))
+    );
+
+    // Fine on its own, but hotter than this device goes
+    status.max_target_temp = 60;
+    assert_eq!(
+        TempBands::default().validate_for(&status),
+        Err(BandError::OutOfRange {
+            min: 19.0,
+            max: 30.0
+        })
+    );
+}
@@ -0,0 +1,188 @@
+use std::time::SystemTime;
+
+use app_lib::{
+    proto::{ButtonCode, Command, TempParam},
+    scheduler::{
+        Day, Due, RuleSpec, ScheduleAction, ScheduleError, ScheduleRule, ScheduleTarget, Trigger,
+    },
+};
+use chrono::{DateTime, FixedOffset, LocalResult, NaiveDate, NaiveDateTime, TimeZone, Utc};
+
+/// 2024-03-06 is a Wednesday
+fn at(day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 3, day, hour, minute, 0).unwrap()
+}
+
+fn spec(days: Vec<Day>, hour: u8, minute: u8) -> RuleSpec {
+    RuleSpec {
+        name: "Wake up warm".to_string(),
+        enabled: true,
+        trigger: Trigger { days, hour, minute },
+        target: ScheduleTarget::Device("bedjet".to_string()),
+        action: ScheduleAction::TargetTemperature(TempParam::Celsius(30.0)),
+    }
+}
+
+fn rule(spec: RuleSpec, updated: DateTime<Utc>) -> ScheduleRule {
+    ScheduleRule {
+        id: "rule".to_string(),
+        updated: updated.into(),
+        spec,
+    }
+}
+
+#[test]
+fn triggers_fire_on_their_days() {
+    let weekdays = Trigger {
+        days: vec![Day::Monday, Day::Friday],
+        hour: 6,
+        minute: 30,
+    };
+    // Wednesday, so the last one was on Monday
+    assert_eq!(weekdays.last_fire(&at(6, 12, 0)), Some(at(4, 6, 30)));
+    // Friday, just before and at the time
+    assert_eq!(weekdays.last_fire(&at(8, 6, 29)), Some(at(4, 6, 30)));
+    assert_eq!(weekdays.last_fire(&at(8, 6, 30)), Some(at(8, 6, 30)));
+    // Monday, a week after the last one
+    assert_eq!(weekdays.last_fire(&at(11, 6, 30)), Some(at(11, 6, 30)));
+
+    let never = Trigger {
+        days: Vec::new(),
+        hour: 6,
+        minute: 30,
+    };
+    assert_eq!(never.last_fire(&at(6, 12, 0)), None);
+}
+
+/// UTC until 2024-03-10 02:00, when the clocks go forward an hour
+#[derive(Debug, Clone, Copy)]
+struct SpringForward;
+
+impl SpringForward {
+    fn change() -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 3, 10)
+            .unwrap()
+            .and_hms_opt(2, 0, 0)
+            .unwrap()
+    }
+
+    fn offset(summer: bool) -> FixedOffset {
+        FixedOffset::east_opt(if summer { 60 * 60 } else { 0 }).unwrap()
+    }
+}
+
+impl TimeZone for SpringForward {
+    type Offset = FixedOffset;
+
+    fn from_offset(_: &FixedOffset) -> Self {
+        SpringForward
+    }
+
+    fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+        self.offset_from_local_datetime(&local.and_hms_opt(0, 0, 0).unwrap())
+    }
+
+    fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+        let change = Self::change();
+        if *local < change {
+            LocalResult::Single(Self::offset(false))
+        } else if *local < change + chrono::Duration::hours(1) {
+            LocalResult::None
+        } else {
+            LocalResult::Single(Self::offset(true))
+        }
+    }
+
+    fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+        self.offset_from_utc_datetime(&utc.and_hms_opt(0, 0, 0).unwrap())
+    }
+
+    fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+        Self::offset(*utc >= Self::change())
+    }
+}
+
+#[test]
+fn triggers_skipped_by_the_clocks_going_forward_fire_when_they_resume() {
+    // 2024-03-10 is a Sunday
+    let trigger = Trigger {
+        days: vec![Day::Sunday],
+        hour: 2,
+        minute: 30,
+    };
+    let local = |day, hour, minute| {
+        SpringForward
+            .with_ymd_and_hms(2024, 3, day, hour, minute, 0)
+            .unwrap()
+    };
+
+    assert_eq!(trigger.last_fire(&local(10, 12, 0)), Some(local(10, 3, 0)));
+    assert_eq!(trigger.last_fire(&local(10, 1, 59)), Some(local(3, 2, 30)));
+}
+
+#[test]
+fn rules_run_once_per_fire_and_catch_up_on_late_ones() {
+    let rule = rule(spec(vec![Day::Wednesday], 22, 0), at(1, 0, 0));
+    let fire: SystemTime = at(6, 22, 0).into();
+
+    assert_eq!(rule.due(&at(6, 21, 59), None), Due::NotYet);
+    assert_eq!(rule.due(&at(6, 22, 0), None), Due::Run(fire));
+    // Already handled
+    assert_eq!(rule.due(&at(6, 22, 1), Some(fire)), Due::NotYet);
+    // The app was opened a little late
+    assert_eq!(rule.due(&at(6, 22, 20), None), Due::Run(fire));
+    // Too late to be any use
+    assert_eq!(rule.due(&at(7, 8, 0), None), Due::Missed(fire));
+    assert_eq!(
+        rule.due(&at(13, 22, 0), Some(fire)),
+        Due::Run(at(13, 22, 0).into())
+    );
+
+    // Never fires for times before it was edited
+    let edited = ScheduleRule {
+        updated: at(6, 22, 5).into(),
+        ..rule.clone()
+    };
+    assert_eq!(edited.due(&at(6, 22, 10), None), Due::NotYet);
+
+    let mut disabled = rule.clone();
+    disabled.spec.enabled = false;
+    assert_eq!(disabled.due(&at(6, 22, 0), None), Due::NotYet);
+}
+
+#[test]
+fn invalid_rules_are_rejected() {
+    assert_eq!(
+        ScheduleRule::new(spec(Vec::new(), 6, 30)).unwrap_err(),
+        ScheduleError::NoDays
+    );
+    assert_eq!(
+        ScheduleRule::new(spec(vec![Day::Monday], 24, 0)).unwrap_err(),
+        ScheduleError::InvalidTime {
+            hour: 24,
+            minute: 0
+        }
+    );
+
+    let mut commands = spec(vec![Day::Monday], 6, 30);
+    commands.action = ScheduleAction::Commands(Vec::new());
+    assert_eq!(
+        ScheduleRule::new(commands.clone()).unwrap_err(),
+        ScheduleError::NoCommands
+    );
+    commands.action = ScheduleAction::Commands(vec![
+        Command::Button(ButtonCode::Cool),
+        Command::Button(ButtonCode::ResetToFactorySettings),
+    ]);
+    assert_eq!(
+        ScheduleRule::new(commands).unwrap_err(),
+        ScheduleError::DestructiveCommand
+    );
+
+    let mut rule = ScheduleRule::new(spec(vec![Day::Monday], 6, 30)).unwrap();
+    assert_eq!(
+        rule.edit(spec(Vec::new(), 6, 30)),
+        Err(ScheduleError::NoDays)
+    );
+    assert_eq!(rule.spec.trigger.days, [Day::Monday]);
+}
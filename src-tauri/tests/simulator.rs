@@ -0,0 +1,687 @@
+use std::time::{Duration, Instant, SystemTime};
+
+use app_lib::{
+    proto::{
+        Biorhythm, BiorhythmProgram, BiorhythmStep, ButtonCode, Command, DeviceSettings,
+        DeviceStatus, Encode, ExpectedStatus, FanParam, OperatingMode, ParsedDeviceStatus,
+        ShutDownCode, TempBands, TempParam, UpdateStatus,
+    },
+    simulator::SimulatedBedJet,
+    state::{
+        fan_out, record_faults, record_history, BedJet, ConfirmationPolicy, DBState, DeviceError,
+        DeviceGroup, FirmwareUpdateOutcome, HistoryFilter, ReconnectPolicy,
+    },
+};
+use futures::future::join_all;
+
+async fn connect(simulator: &SimulatedBedJet) -> BedJet {
+    let device = BedJet::new(simulator.clone(), None).await.unwrap();
+    device.listen_status().await.unwrap();
+    device
+}
+
+async fn wait_for_status(device: &BedJet, f: impl FnMut(&DeviceStatus) -> bool) -> DeviceStatus {
+    let mut f = f;
+    let mut recv = device.subscribe_status();
+    let status = tokio::time::timeout(
+        Duration::from_secs(1),
+        recv.wait_for(|i| i.as_ref().is_some_and(&mut f)),
+    )
+    .await
+    .expect("Timed out waiting for the status")
+    .unwrap();
+    status.unwrap()
+}
+
+#[tokio::test]
+async fn reports_status_after_connecting() {
+    let simulator = SimulatedBedJet::new("bedjet", "Bedroom");
+    let device = connect(&simulator).await;
+
+    let status = device.get_status().await.unwrap();
+    assert_eq!(status, simulator.status());
+    assert_eq!(device.get_friendly_name().await.unwrap(), "Bedroom");
+}
+
+#[tokio::test]
+async fn stopped_devices_no_longer_follow_the_status() {
+    let simulator = SimulatedBedJet::new("bedjet", "Bedroom");
+    let kept = connect(&simulator).await;
+    let dropped = connect(&simulator).await;
+    dropped.get_status().await.unwrap();
+    dropped.stop_notifications().await;
+
+    simulator.update_status(|i| i.fan_step = 3);
+    wait_for_status(&kept, |i| i.fan_step == 3).await;
+    assert!(dropped.current_status().is_some_and(|i| i.fan_step != 3));
+}
+
+#[tokio::test]
+async fn commands_update_the_status() {
+    let simulator = SimulatedBedJet::new("bedjet", "Bedroom");
+    let device = connect(&simulator).await;
+
+    device
+        .send_command(Command::Button(ButtonCode::Cool))
+        .await
+        .unwrap();
+    device
+        .send_command(Command::SetTemp(TempParam::Celsius(21.0)))
+        .await
+        .unwrap();
+    device
+        .send_command(Command::SetFan(FanParam::Percent(50)))
+        .await
+        .unwrap();
+
+    let status = wait_for_status(&device, |i| i.fan_step == 9 && i.target_temp == 42).await;
+    assert_eq!(status.operating_mode, OperatingMode::Cool);
+    assert_eq!(status.remaining_hours, 12);
+}
+
+#[tokio::test]
+async fn switching_modes_keeps_the_fan_and_time_when_restored() {
+    let simulator = SimulatedBedJet::new("bedjet", "Bedroom");
+    let device = connect(&simulator).await;
+    device
+        .send_command(Command::Button(ButtonCode::Cool))
+        .await
+        .unwrap();
+    device
+        .send_command(Command::SetTime {
+            hours: 2,
+            minutes: 30,
+        })
+        .await
+        .unwrap();
+    device
+        .send_command(Command::SetFan(FanParam::Step(15)))
+        .await
+        .unwrap();
+    let before = wait_for_status(&device, |i| i.fan_step == 15).await;
+
+    // Switching modes resets the runtime, so it has to be restored along with the fan
+    for command in [
+        Command::Button(ButtonCode::ExternalHeat),
+        Command::SetTime {
+            hours: before.remaining_hours,
+            minutes: before.remaining_minutes,
+        },
+        Command::SetFan(FanParam::Step(before.fan_step)),
+    ] {
+        device.send_command(command).await.unwrap();
+    }
+
+    let status = wait_for_status(&device, |i| {
+        i.operating_mode == OperatingMode::ExtendedHeat && i.remaining_hours == 2
+    })
+    .await;
+    assert_eq!(status.remaining_minutes, 30);
+    assert_eq!(status.fan_step, 15);
+}
+
+#[tokio::test]
+async fn reads_and_writes_parameters() {
+    let simulator = SimulatedBedJet::new("bedjet", "Bedroom");
+    let device = connect(&simulator).await;
+
+    device.set_device_name("Guest Room").await.unwrap();
+    assert_eq!(device.get_device_name().await.unwrap(), "Guest Room");
+    assert_eq!(device.get_friendly_name().await.unwrap(), "Guest Room");
+
+    device
+        .rename_memory_preset(2, "Nap".to_string())
+        .await
+        .unwrap();
+    assert_eq!(device.get_memory_preset(2).await.unwrap().name, "Nap");
+    assert_eq!(device.get_memory_preset(1).await.unwrap().name, "Memory 1");
+
+    let firmware = device.get_firmware_version().await.unwrap();
+    assert_eq!(firmware.main.major, 3);
+}
+
+#[tokio::test]
+async fn biorhythms_round_trip_through_the_device() {
+    let simulator = SimulatedBedJet::new("bedjet", "Bedroom");
+    let device = connect(&simulator).await;
+
+    let program = BiorhythmProgram {
+        slot: 3,
+        name: "Winter".to_string(),
+        biorhythm: Biorhythm {
+            steps: vec![
+                BiorhythmStep {
+                    operating_mode: OperatingMode::ExtendedHeat,
+                    target_temp: 64,
+                    fan_step: 10,
+                    hours: 0,
+                    minutes: 45,
+                },
+                BiorhythmStep {
+                    operating_mode: OperatingMode::Cool,
+                    target_temp: 40,
+                    fan_step: 4,
+                    hours: 6,
+                    minutes: 0,
+                },
+            ],
+        },
+    };
+    device.set_biorhythm(&program).await.unwrap();
+
+    assert_eq!(device.get_biorhythm(3).await.unwrap(), program);
+}
+
+#[tokio::test]
+async fn memory_presets_restore_the_stored_state() {
+    let simulator = SimulatedBedJet::new("bedjet", "Bedroom");
+    let device = connect(&simulator).await;
+    device
+        .send_command(Command::Button(ButtonCode::Dry))
+        .await
+        .unwrap();
+    device
+        .send_command(Command::SetFan(FanParam::Step(19)))
+        .await
+        .unwrap();
+    device.store_memory_preset(1).await.unwrap();
+
+    device
+        .send_command(Command::Button(ButtonCode::Stop))
+        .await
+        .unwrap();
+    wait_for_status(&device, |i| i.operating_mode == OperatingMode::Standby).await;
+
+    device.recall_memory_preset(1).await.unwrap();
+    let status = wait_for_status(&device, |i| i.operating_mode == OperatingMode::Dry).await;
+    assert_eq!(status.fan_step, 19);
+}
+
+#[tokio::test]
+async fn applies_settings() {
+    let simulator = SimulatedBedJet::new("bedjet", "Bedroom");
+    let device = connect(&simulator).await;
+    let settings = DeviceSettings {
+        ring_light: false,
+        beeper_muted: true,
+        low_power_mode: true,
+        radio_enabled: true,
+    };
+
+    device.apply_settings(&settings).await.unwrap();
+
+    assert_eq!(simulator.settings(), settings);
+}
+
+#[tokio::test]
+async fn provisions_wifi() {
+    let simulator = SimulatedBedJet::new("bedjet", "Bedroom");
+    let device = connect(&simulator).await;
+
+    device
+        .provision_wifi("home", "correct horse", None)
+        .await
+        .unwrap();
+
+    assert_eq!(
+        simulator.wifi_credentials(),
+        ("home".to_string(), "correct horse".to_string())
+    );
+    assert_eq!(
+        simulator.status().update_status,
+        UpdateStatus::ConnectionTestOK
+    );
+}
+
+#[tokio::test]
+async fn wifi_test_waits_for_its_own_result() {
+    let simulator = SimulatedBedJet::new("bedjet", "Bedroom");
+    let device = connect(&simulator).await;
+    // Left over from an earlier test that passed
+    simulator.update_status(|i| i.update_status = UpdateStatus::ConnectionTestOK);
+    wait_for_status(&device, |i| {
+        i.update_status == UpdateStatus::ConnectionTestOK
+    })
+    .await;
+    simulator.set_wifi_reachable(false);
+
+    let result = device.provision_wifi("home", "correct horse", None).await;
+    assert!(matches!(
+        result,
+        Err(DeviceError::WifiTestFailed(UpdateStatus::UnableToConnect))
+    ));
+}
+
+#[tokio::test]
+async fn rejects_invalid_wifi_credentials() {
+    let simulator = SimulatedBedJet::new("bedjet", "Bedroom");
+    let device = connect(&simulator).await;
+
+    let result = device.provision_wifi("home", "short", None).await;
+
+    assert!(matches!(result, Err(DeviceError::InterfaceError(_))));
+    assert!(simulator.commands().is_empty());
+}
+
+#[tokio::test]
+async fn firmware_update_needs_wifi() {
+    let simulator = SimulatedBedJet::new("bedjet", "Bedroom");
+    let device = connect(&simulator).await;
+
+    let result = device.update_firmware(None).await;
+    assert!(matches!(
+        result,
+        Err(DeviceError::FirmwareUpdateFailed(
+            UpdateStatus::NoWiFiConfig
+        ))
+    ));
+
+    device
+        .provision_wifi("home", "correct horse", None)
+        .await
+        .unwrap();
+    let outcome = device.update_firmware(None).await.unwrap();
+    assert_eq!(outcome, FirmwareUpdateOutcome::UpToDate);
+}
+
+#[tokio::test]
+async fn firmware_update_waits_for_its_own_result() {
+    let simulator = SimulatedBedJet::new("bedjet", "Bedroom");
+    let device = connect(&simulator).await;
+    simulator.set_wifi_reachable(false);
+    let result = device.provision_wifi("home", "correct horse", None).await;
+    assert!(matches!(result, Err(DeviceError::WifiTestFailed(_))));
+
+    // The failed test is still showing when the update starts
+    simulator.set_wifi_reachable(true);
+    let outcome = device.update_firmware(None).await.unwrap();
+    assert_eq!(outcome, FirmwareUpdateOutcome::UpToDate);
+}
+
+fn fast_reconnect(max_attempts: u32) -> ReconnectPolicy {
+    ReconnectPolicy {
+        initial_delay: Duration::from_millis(1),
+        max_delay: Duration::from_millis(5),
+        max_attempts,
+    }
+}
+
+#[test]
+fn reconnect_delay_backs_off_exponentially() {
+    let policy = ReconnectPolicy {
+        initial_delay: Duration::from_secs(1),
+        max_delay: Duration::from_secs(60),
+        max_attempts: 10,
+    };
+    let delays: Vec<u64> = (1..=8).map(|i| policy.delay(i).as_secs()).collect();
+    assert_eq!(delays, [1, 2, 4, 8, 16, 32, 60, 60]);
+    assert_eq!(policy.delay(u32::MAX), policy.max_delay);
+}
+
+#[tokio::test]
+async fn reconnects_and_listens_to_the_status_again() {
+    let simulator = SimulatedBedJet::new("bedjet", "Bedroom");
+    let device = connect(&simulator).await;
+
+    simulator.drop_connection();
+    device.reconnect(fast_reconnect(3), None).await.unwrap();
+
+    simulator.update_status(|status| status.fan_step = 3);
+    let status = wait_for_status(&device, |i| i.fan_step == 3).await;
+    assert_eq!(status, simulator.status());
+}
+
+#[tokio::test]
+async fn keeps_trying_until_the_device_is_back_in_range() {
+    let simulator = SimulatedBedJet::new("bedjet", "Bedroom");
+    let device = connect(&simulator).await;
+
+    simulator.set_in_range(false);
+    let reconnect = {
+        let device = device.clone();
+        tokio::spawn(async move { device.reconnect(fast_reconnect(100), None).await })
+    };
+    tokio::time::sleep(Duration::from_millis(20)).await;
+    assert!(!reconnect.is_finished());
+
+    simulator.set_in_range(true);
+    reconnect.await.unwrap().unwrap();
+    simulator.update_status(|status| status.fan_step = 3);
+    wait_for_status(&device, |i| i.fan_step == 3).await;
+}
+
+#[tokio::test]
+async fn gives_up_reconnecting_out_of_range() {
+    let simulator = SimulatedBedJet::new("bedjet", "Bedroom");
+    let device = connect(&simulator).await;
+
+    simulator.set_in_range(false);
+    let result = device.reconnect(fast_reconnect(3), None).await;
+    assert!(matches!(result, Err(DeviceError::ReconnectFailed)));
+}
+
+#[tokio::test]
+async fn confirms_commands_from_the_status() {
+    let simulator = SimulatedBedJet::new("bedjet", "Bedroom");
+    let device = connect(&simulator).await;
+
+    let command = Command::SetTemp(TempParam::Celsius(20.0));
+    let expected = command.expected_status().unwrap();
+    let status = device
+        .send_command_confirmed(command, expected, ConfirmationPolicy::default())
+        .await
+        .unwrap();
+
+    assert_eq!(status.target_temp, 40);
+    assert_eq!(simulator.commands().len(), 1);
+}
+
+#[tokio::test]
+async fn retries_commands_that_dont_take_effect() {
+    let simulator = SimulatedBedJet::new("bedjet", "Bedroom");
+    let device = connect(&simulator).await;
+
+    let policy = ConfirmationPolicy {
+        attempts: 2,
+        timeout: Duration::from_millis(50),
+    };
+    let result = device
+        .send_command_confirmed(
+            Command::SetTemp(TempParam::Celsius(20.0)),
+            ExpectedStatus::TargetTemp(TempParam::Celsius(30.0)),
+            policy,
+        )
+        .await;
+
+    assert!(matches!(
+        result,
+        Err(DeviceError::NotAcknowledged { attempts: 2 })
+    ));
+    assert_eq!(simulator.commands().len(), 2);
+}
+
+#[tokio::test]
+async fn queues_commands_in_order_and_merges_set_points() {
+    let simulator = SimulatedBedJet::new("bedjet", "Bedroom");
+    let device = connect(&simulator).await;
+
+    let started = Instant::now();
+    let commands = [
+        Command::Button(ButtonCode::Cool),
+        Command::SetTemp(TempParam::Celsius(20.0)),
+        Command::SetTemp(TempParam::Celsius(21.0)),
+        Command::SetTemp(TempParam::Celsius(22.0)),
+        Command::SetFan(FanParam::Step(4)),
+    ];
+    let results = join_all(commands.map(|i| device.send_command(i))).await;
+    assert!(results.iter().all(|i| i.is_ok()));
+
+    let sent = [
+        Command::Button(ButtonCode::Cool),
+        Command::SetTemp(TempParam::Celsius(22.0)),
+        Command::SetFan(FanParam::Step(4)),
+    ]
+    .map(|i| i.encode().unwrap());
+    assert_eq!(simulator.commands(), sent);
+    // Each write after the first waits out the gap
+    assert!(started.elapsed() >= Duration::from_millis(200));
+}
+
+#[tokio::test]
+async fn target_temperature_switches_modes() {
+    let simulator = SimulatedBedJet::new("bedjet", "Bedroom");
+    let device = connect(&simulator).await;
+    device
+        .send_command(Command::Button(ButtonCode::Cool))
+        .await
+        .unwrap();
+    device
+        .send_command(Command::SetTime {
+            hours: 2,
+            minutes: 30,
+        })
+        .await
+        .unwrap();
+    device
+        .send_command(Command::SetFan(FanParam::Step(4)))
+        .await
+        .unwrap();
+    wait_for_status(&device, |i| i.fan_step == 4 && i.remaining_hours == 2).await;
+
+    device
+        .set_target_temperature(TempParam::Celsius(31.0), &TempBands::default())
+        .await
+        .unwrap();
+
+    let status = wait_for_status(&device, |i| i.target_temp == 62).await;
+    assert_eq!(status.operating_mode, OperatingMode::ExtendedHeat);
+    assert_eq!(status.fan_step, 4);
+    assert_eq!((status.remaining_hours, status.remaining_minutes), (2, 30));
+}
+
+#[tokio::test]
+async fn target_temperatures_dont_hold_up_commands_without_a_status() {
+    let simulator = SimulatedBedJet::new("bedjet", "Bedroom");
+    // Never listens to the status, so it never gets one
+    let device = BedJet::new(simulator.clone(), None).await.unwrap();
+
+    let pending = {
+        let device = device.clone();
+        tokio::spawn(async move {
+            device
+                .set_target_temperature(TempParam::Celsius(22.0), &TempBands::default())
+                .await
+        })
+    };
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    tokio::time::timeout(
+        Duration::from_secs(1),
+        device.send_command(Command::Button(ButtonCode::Cool)),
+    )
+    .await
+    .expect("The command waited behind the target temperature")
+    .unwrap();
+    assert!(matches!(pending.await.unwrap(), Err(DeviceError::Timeout)));
+}
+
+#[tokio::test]
+async fn records_status_changes_to_the_history() {
+    let simulator = SimulatedBedJet::new("bedjet", "Bedroom");
+    let device = connect(&simulator).await;
+    let db = DBState::new(sled::Config::new().temporary(true).open().unwrap());
+    let start = SystemTime::now() - Duration::from_secs(1);
+    tokio::spawn(record_history(
+        db.clone(),
+        device.id.clone(),
+        device.subscribe_status(),
+    ));
+
+    device
+        .send_command(Command::SetTemp(TempParam::Celsius(21.0)))
+        .await
+        .unwrap();
+    wait_for_status(&device, |i| i.target_temp == 42).await;
+
+    let end = SystemTime::now() + Duration::from_secs(1);
+    let history = tokio::time::timeout(Duration::from_secs(1), async {
+        loop {
+            let history = db.get_history(&device.id, start, end).unwrap();
+            let current = device.current_status().map(ParsedDeviceStatus::from);
+            if history.last().map(|i| i.status) == current {
+                return history;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("Timed out waiting for the history");
+    assert!(history
+        .windows(2)
+        .all(|i| i[0].time <= i[1].time && i[0].status != i[1].status));
+    assert!(db.get_history("other", start, end).unwrap().is_empty());
+}
+
+#[test]
+fn the_countdown_alone_isnt_recorded() {
+    let db = DBState::new(sled::Config::new().temporary(true).open().unwrap());
+    let mut status = SimulatedBedJet::new("bedjet", "Bedroom").status();
+    let start = SystemTime::UNIX_EPOCH + Duration::from_secs(1000);
+    let mut filter = HistoryFilter::default();
+    let mut report = |time: SystemTime, status: &DeviceStatus| {
+        if filter.should_record(time, status) {
+            db.record_status("bedjet", time, status).unwrap();
+        }
+    };
+
+    // All in the same millisecond, which mustn't overwrite what's already recorded
+    for seconds in (50..60).rev() {
+        status.remaining_seconds = seconds;
+        report(start, &status);
+    }
+    status.target_temp = 42;
+    report(start, &status);
+    // Saved again once the heartbeat's due, even though only the countdown changed
+    status.remaining_seconds = 40;
+    report(start + Duration::from_secs(30), &status);
+    report(start + Duration::from_secs(90), &status);
+
+    let history = db
+        .get_history("bedjet", start, start + Duration::from_secs(90))
+        .unwrap();
+    let saved: Vec<_> = history
+        .iter()
+        .map(|i| {
+            (
+                i.status.remaining_duration.as_secs() % 60,
+                i.status.target_temp,
+            )
+        })
+        .collect();
+    assert_eq!(saved, [(59, 22.0), (50, 21.0), (40, 21.0)]);
+}
+
+#[test]
+fn history_is_kept_by_time_and_pruned() {
+    let db = DBState::new(sled::Config::new().temporary(true).open().unwrap());
+    let status = SimulatedBedJet::new("bedjet", "Bedroom").status();
+    let at = |secs| SystemTime::UNIX_EPOCH + Duration::from_secs(secs);
+    for secs in [300, 100, 200] {
+        db.record_status("bedjet", at(secs), &status).unwrap();
+    }
+
+    let times = |from, to| -> Vec<SystemTime> {
+        db.get_history("bedjet", at(from), at(to))
+            .unwrap()
+            .into_iter()
+            .map(|i| i.time)
+            .collect()
+    };
+    assert_eq!(times(0, 1000), [at(100), at(200), at(300)]);
+    assert_eq!(times(150, 300), [at(200), at(300)]);
+
+    assert_eq!(db.prune_history(at(250)).unwrap(), 2);
+    assert_eq!(times(0, 1000), [at(300)]);
+}
+
+#[tokio::test]
+async fn reports_each_fault_once_and_logs_it() {
+    let simulator = SimulatedBedJet::new("bedjet", "Bedroom");
+    let device = connect(&simulator).await;
+    let db = DBState::new(sled::Config::new().temporary(true).open().unwrap());
+    let mut faults = device.subscribe_faults();
+    tokio::spawn(record_faults(db.clone(), None, device.subscribe_faults()));
+
+    let report = |code, fan_step| {
+        simulator.update_status(|i| {
+            i.shutdown_code = code;
+            i.fan_step = fan_step;
+        });
+        wait_for_status(&device, move |i| {
+            i.shutdown_code == code && i.fan_step == fan_step
+        })
+    };
+    report(ShutDownCode::FanFailure, 1).await;
+    // Still the same fault, so it isn't reported again
+    report(ShutDownCode::FanFailure, 2).await;
+    report(ShutDownCode::Normal, 3).await;
+    report(ShutDownCode::FastOverTempTrip, 4).await;
+
+    for code in [ShutDownCode::FanFailure, ShutDownCode::FastOverTempTrip] {
+        let fault = tokio::time::timeout(Duration::from_secs(1), faults.recv())
+            .await
+            .expect("Timed out waiting for the fault")
+            .unwrap();
+        assert_eq!(fault.code, code);
+    }
+    assert!(faults.try_recv().is_err());
+
+    let logged = tokio::time::timeout(Duration::from_secs(1), async {
+        loop {
+            let logged = db.get_faults(Some(&device.id)).unwrap();
+            if logged.len() == 2 {
+                return logged;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("Timed out waiting for the fault log");
+    assert_eq!(
+        logged.iter().map(|i| i.code).collect::<Vec<_>>(),
+        [ShutDownCode::FastOverTempTrip, ShutDownCode::FanFailure]
+    );
+    assert!(db.get_faults(Some("other")).unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn group_commands_report_each_device() {
+    let db = DBState::new(sled::Config::new().temporary(true).open().unwrap());
+    let group = DeviceGroup::new(
+        "Bed".to_string(),
+        vec![
+            "left".to_string(),
+            "right".to_string(),
+            "left".to_string(),
+            "guest".to_string(),
+        ],
+    );
+    assert_eq!(group.members, ["left", "right", "guest"]);
+    db.set_group(&group).unwrap();
+    assert_eq!(db.get_groups(), [group.clone()]);
+
+    let left = SimulatedBedJet::new("left", "Left");
+    let right = SimulatedBedJet::new("right", "Right");
+    let connected = [connect(&left).await, connect(&right).await];
+    // The guest bed isn't connected, which shouldn't stop the others
+    let devices = db
+        .get_group(&group.id)
+        .unwrap()
+        .members
+        .into_iter()
+        .map(|id| {
+            let device = connected.iter().find(|i| i.id == id).cloned();
+            (id, device.ok_or(DeviceError::DeviceNotFound))
+        })
+        .collect();
+
+    let bands = &TempBands::default();
+    let results = fan_out(devices, |device| async move {
+        device
+            .set_target_temperature(TempParam::Celsius(21.0), bands)
+            .await
+    })
+    .await;
+    let results: Vec<(&str, bool)> = results
+        .iter()
+        .map(|(id, result)| (id.as_str(), result.is_ok()))
+        .collect();
+    assert_eq!(results, [("left", true), ("right", true), ("guest", false)]);
+    for device in &connected {
+        wait_for_status(device, |i| i.target_temp == 42).await;
+    }
+
+    assert!(db.delete_group(&group.id).unwrap());
+    assert!(!db.delete_group(&group.id).unwrap());
+    assert!(db.get_groups().is_empty());
+}
@@ -1,24 +1,35 @@
-import { ActionIcon, AppShell, Container, Group, MantineProvider, Select, Space, Stack, Text } from '@mantine/core';
+import { ActionIcon, Alert, AppShell, Container, Group, MantineProvider, NumberInput, Select, Space, Stack, Text } from '@mantine/core';
 import { useState } from 'react';
+import { useQueryClient } from '@tanstack/react-query';
 import { secondsToHHMM } from './util';
 import TempSlider from './components/TempSlider';
 import FanSlider from './components/FanSlider';
 import { DeviceList } from './components/DeviceList';
 import { ModeControl } from './components/ModeControl';
-import { useAdapters, useConfig, useDeviceStatus } from './hooks';
+import TempBandsEditor from './components/TempBandsEditor';
+import ExportHistory from './components/ExportHistory';
+import ScheduleEditor from './components/ScheduleEditor';
+import GroupsEditor from './components/GroupsEditor';
+import { useAdapters, useConfig, useDeviceStatus, useFaults } from './hooks';
 import { useHashContext } from './context/HashContext';
 import { match } from 'ts-pattern';
 import { IconSettings, IconHome, IconClock } from '@tabler/icons-react';
 
-import { TemperatureUnit, UserPreferences } from './types';
-import { set_config } from './commands';
+import { AutoconnectPreference, TemperatureUnit, UserPreferences } from './types';
+import { errorMessage, select_adapter, set_config } from './commands';
 
 function MainPage({ id }: { id: string | null }) {
   const status = useDeviceStatus(id);
+  const faults = useFaults(id);
+  const fault = faults.data?.[0];
   return (
     <Container>
       {!!id &&
         <Stack pt={"lg"}>
+          {fault && status.data?.shutdown_code === fault.code &&
+            <Alert color="red" title="Device Fault">
+              {fault.code} at {new Date(fault.time * 1000).toLocaleString()}
+            </Alert>}
           <ModeControl bedjet={id} data={status.data} />
           <div>
             <Group>
@@ -30,6 +41,8 @@ function MainPage({ id }: { id: string | null }) {
           <TempSlider bedjet={id} data={status.data} />
           <Space />
           <FanSlider bedjet={id} data={status.data} />
+          <Space />
+          <ExportHistory bedjet={id} />
         </Stack>
       }
     </Container>
@@ -39,11 +52,21 @@ function MainPage({ id }: { id: string | null }) {
 function SettingsPage() {
   const config = useConfig();
   const adapters = useAdapters();
+  const queryClient = useQueryClient();
+  const [error, setError] = useState<string | null>(null);
 
   if (!config.data) {
     return <p>Loading...</p>
   }
 
+  // Shows what the backend saved rather than what was picked, so the two can't disagree
+  const saveConfig = (changes: Partial<UserPreferences>) => {
+    set_config({ ...config.data, ...changes } as UserPreferences)
+      .then(() => setError(null))
+      .catch((err) => setError(errorMessage(err)))
+      .finally(() => config.refetch())
+  }
+
   return (
     <Container>
       <Select
@@ -51,11 +74,15 @@ function SettingsPage() {
         data={adapters.data?.adapters ?? []}
         value={adapters.data?.selected}
         label="Bluetooth Adapter"
+        placeholder="No Bluetooth adapter found"
         allowDeselect={false}
         onChange={(adapter) => {
           if (!adapter) return
-          set_config({ ...config.data, adapter } as UserPreferences)
-          config.refetch()
+          select_adapter(adapter).then(() => {
+            adapters.refetch()
+            config.refetch()
+            queryClient.invalidateQueries({ queryKey: ["devices"] })
+          })
         }}
       />
       <Select label="Units"
@@ -65,10 +92,38 @@ function SettingsPage() {
         onChange={(unit) => {
           if (!unit) return;
 
-          set_config({ ...config.data, unit } as UserPreferences)
-          config.refetch()
+          saveConfig({ unit: unit as TemperatureUnit })
+        }}
+      />
+      <Select label="Connect on Startup"
+        data={[
+          { value: AutoconnectPreference.All, label: "All known devices" },
+          { value: AutoconnectPreference.LastUsed, label: "Last used device" },
+          { value: AutoconnectPreference.None, label: "None" },
+        ]}
+        allowDeselect={false}
+        value={config.data.autoconnect}
+        onChange={(autoconnect) => {
+          if (!autoconnect) return;
+
+          saveConfig({ autoconnect: autoconnect as AutoconnectPreference })
+        }}
+      />
+      <NumberInput label="Days of History to Keep"
+        description="Zero keeps all of it"
+        min={0}
+        allowDecimal={false}
+        value={config.data.history_retention_days}
+        onChange={(days) => {
+          if (typeof days !== "number") return;
+
+          saveConfig({ history_retention_days: days })
         }}
       />
+      {error && <Text size="sm" c="red">{error}</Text>}
+      <TempBandsEditor config={config.data} onSaved={() => config.refetch()} />
+      <GroupsEditor config={config.data} />
+      <ScheduleEditor config={config.data} />
     </Container>
   )
 }
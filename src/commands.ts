@@ -1,18 +1,46 @@
 import { invoke } from "@tauri-apps/api/core";
 import {
   AdapterResult,
+  BiorhythmProgram,
   Command,
+  CommandError,
+  DeviceFault,
+  DeviceGroup,
+  DeviceInfo,
+  DeviceSettings,
+  ExpectedStatus,
+  ExportFormat,
+  FirmwareUpdateOutcome,
+  GroupResult,
+  KnownDevice,
+  MemoryPreset,
+  NightSummary,
   ParsedDeviceStatus,
   PeripheralResult,
+  RuleSpec,
+  ScheduleEntry,
+  ScheduleRule,
+  StatusSample,
+  TempParam,
+  TimeRange,
   UserPreferences,
 } from "./types";
 import { Store } from "@tauri-apps/plugin-store";
 
+/** Commands reject with a `CommandError`, this pulls the message out of one to show to the user */
+export function errorMessage(error: unknown): string {
+  const err = error as Partial<CommandError> | undefined;
+  return typeof err?.value === "string" ? err.value : String(error);
+}
 
 export async function get_adapters(): Promise<AdapterResult> {
   return invoke("get_btle_adapters");
 }
 
+export async function select_adapter(adapter: string): Promise<void> {
+  return invoke("select_adapter", { adapter });
+}
+
 export async function scan_devices(): Promise<PeripheralResult[]> {
   return invoke("scan_devices");
 }
@@ -25,23 +53,236 @@ export async function disconnect_device(id: string): Promise<void> {
   return invoke("disconnect_device", { id });
 }
 
+export async function get_known_devices(): Promise<KnownDevice[]> {
+  return invoke("get_known_devices");
+}
+
+/** The logged faults, most recent first. Only the device's faults if an id is given */
+export async function get_faults(id?: string): Promise<DeviceFault[]> {
+  return invoke("get_faults", { id });
+}
+
+export async function get_auto_reconnect(id: string): Promise<boolean> {
+  return invoke("get_auto_reconnect", { id });
+}
+
+export async function set_auto_reconnect(
+  id: string,
+  enabled: boolean
+): Promise<void> {
+  return invoke("set_auto_reconnect", { id, enabled });
+}
+
 export async function send_command(id: string, command: Command) {
   await invoke("send_command", { id, command });
 }
 
+/** Sets the target temperature, switching between cool, dry and extended heat to reach it */
+export async function set_target_temperature(
+  id: string,
+  temp: TempParam
+): Promise<void> {
+  return invoke("set_target_temperature", { id, temp });
+}
+
+export async function get_groups(): Promise<DeviceGroup[]> {
+  return invoke("get_groups");
+}
+
+export async function create_group(name: string, members: string[]): Promise<DeviceGroup> {
+  return invoke("create_group", { name, members });
+}
+
+export async function update_group(
+  id: string,
+  name: string,
+  members: string[]
+): Promise<DeviceGroup> {
+  return invoke("update_group", { id, name, members });
+}
+
+export async function delete_group(id: string): Promise<void> {
+  return invoke("delete_group", { id });
+}
+
+/** Sends the command to every device in the group at once, resolving with how it went on each one */
+export async function send_group_command(
+  group: string,
+  command: Command
+): Promise<GroupResult[]> {
+  return invoke("send_group_command", { group, command });
+}
+
+/** Sets the same target temperature on every device in the group, resolving with how it went on each one */
+export async function set_group_temperature(
+  group: string,
+  temp: TempParam
+): Promise<GroupResult[]> {
+  return invoke("set_group_temperature", { group, temp });
+}
+
+/** Resolves once the device reports the expected change, which is worked out from the command when left out */
+export async function send_command_confirmed(
+  id: string,
+  command: Command,
+  expected?: ExpectedStatus
+): Promise<ParsedDeviceStatus> {
+  return invoke("send_command_confirmed", { id, command, expected });
+}
+
 export async function get_status(
   id: string,
 ): Promise<ParsedDeviceStatus | undefined> {
   return invoke("get_status", { id });
 }
 
+/** `from` and `to` are seconds since the unix epoch */
+export async function get_history(
+  id: string,
+  from: number,
+  to: number,
+): Promise<StatusSample[]> {
+  return invoke("get_history", { id, from, to });
+}
 
-const store = new Store(".settings.dat");
+/** `date` is the day the night starts on, as `YYYY-MM-DD` */
+export async function get_night_summary(
+  id: string,
+  date: string,
+): Promise<NightSummary> {
+  return invoke("get_night_summary", { id, date });
+}
+
+/** Writes the recorded history to `path`, and resolves with how many samples were written */
+export async function export_history(
+  id: string,
+  range: TimeRange,
+  format: ExportFormat,
+  path: string,
+): Promise<number> {
+  return invoke("export_history", { id, range, format, path });
+}
 
-export async function get_config(): Promise<UserPreferences | null> {
-  return store.get("config");
+export async function get_device_info(id: string): Promise<DeviceInfo> {
+  return invoke("get_device_info", { id });
+}
+
+export async function set_device_name(id: string, name: string): Promise<void> {
+  return invoke("set_device_name", { id, name });
+}
+
+export async function get_device_settings(
+  id: string,
+): Promise<DeviceSettings> {
+  return invoke("get_device_settings", { id });
 }
 
+export async function apply_device_settings(
+  id: string,
+  settings: DeviceSettings,
+): Promise<void> {
+  return invoke("apply_device_settings", { id, settings });
+}
+
+export async function request_factory_reset(id: string): Promise<string> {
+  return invoke("request_factory_reset", { id });
+}
+
+export async function factory_reset(
+  id: string,
+  confirmationToken: string,
+): Promise<void> {
+  return invoke("factory_reset", { id, confirmationToken });
+}
+
+export async function get_biorhythms(id: string): Promise<BiorhythmProgram[]> {
+  return invoke("get_biorhythms", { id });
+}
+
+export async function set_biorhythm(
+  id: string,
+  program: BiorhythmProgram,
+): Promise<void> {
+  return invoke("set_biorhythm", { id, program });
+}
+
+export async function get_memory_presets(id: string): Promise<MemoryPreset[]> {
+  return invoke("get_memory_presets", { id });
+}
+
+export async function store_memory_preset(
+  id: string,
+  slot: number,
+): Promise<void> {
+  return invoke("store_memory_preset", { id, slot });
+}
+
+export async function rename_memory_preset(
+  id: string,
+  slot: number,
+  name: string,
+): Promise<void> {
+  return invoke("rename_memory_preset", { id, slot, name });
+}
+
+export async function recall_memory_preset(
+  id: string,
+  slot: number,
+): Promise<void> {
+  return invoke("recall_memory_preset", { id, slot });
+}
+
+export async function provision_wifi(
+  id: string,
+  ssid: string,
+  password: string,
+): Promise<void> {
+  return invoke("provision_wifi", { id, ssid, password });
+}
+
+export async function update_firmware(
+  id: string,
+): Promise<FirmwareUpdateOutcome> {
+  return invoke("update_firmware", { id });
+}
+
+/** Where the frontend used to keep its own copy of the settings, which the backend never saw */
+const legacyStore = new Store(".settings.dat");
+
+export async function get_config(): Promise<UserPreferences> {
+  // Only the unit was ever picked there, so it's moved over to the backend once
+  const legacy = await legacyStore.get<UserPreferences>("config");
+  if (legacy) {
+    const config: UserPreferences = await invoke("get_config");
+    await set_config({ ...config, unit: legacy.unit });
+    await legacyStore.delete("config");
+    await legacyStore.save();
+  }
+  return invoke("get_config");
+}
+
+/** Rejects with an `InvalidParameter` error when the temperature bands don't fit a connected device */
 export async function set_config(config: UserPreferences): Promise<void> {
-    await store.set("config", config);
+  return invoke("set_config", { config });
+}
+
+/** Every scheduled rule, along with how it went the last time it fired */
+export async function get_schedule(): Promise<ScheduleEntry[]> {
+  return invoke("get_schedule");
+}
+
+export async function create_schedule_rule(spec: RuleSpec): Promise<ScheduleRule> {
+  return invoke("create_schedule_rule", { spec });
+}
+
+/** The rule won't fire for times before the edit */
+export async function update_schedule_rule(
+  id: string,
+  spec: RuleSpec,
+): Promise<ScheduleRule> {
+  return invoke("update_schedule_rule", { id, spec });
+}
+
+export async function delete_schedule_rule(id: string): Promise<void> {
+  return invoke("delete_schedule_rule", { id });
 }
@@ -0,0 +1,58 @@
+import { Button, Group, NumberInput, Select, Text } from "@mantine/core";
+import { save } from "@tauri-apps/plugin-dialog";
+import { useState } from "react";
+import { errorMessage, export_history } from "../commands";
+import { ExportFormat } from "../types";
+
+const Formats = [
+    { value: ExportFormat.Csv, label: "CSV", extension: "csv" },
+    { value: ExportFormat.JsonLines, label: "JSON Lines", extension: "jsonl" },
+];
+
+interface ExportHistoryProps {
+    bedjet: string
+}
+
+export default function ExportHistory({ bedjet }: ExportHistoryProps) {
+    const [format, setFormat] = useState(ExportFormat.Csv);
+    const [days, setDays] = useState(7);
+    const [message, setMessage] = useState<string | null>(null);
+
+    const exportHistory = async () => {
+        const extension = Formats.find((i) => i.value === format)!.extension;
+        const path = await save({
+            defaultPath: `betterjet-history.${extension}`,
+            filters: [{ name: format, extensions: [extension] }],
+        });
+        if (!path) return;
+
+        const to = Math.floor(Date.now() / 1000);
+        const from = to - days * 24 * 60 * 60;
+        export_history(bedjet, { from, to }, format, path)
+            .then((count) => setMessage(`Exported ${count} samples`))
+            .catch((err) => setMessage(errorMessage(err)));
+    };
+
+    return (
+        <div>
+            <Group align="end">
+                <Select
+                    label="Export History"
+                    data={Formats}
+                    allowDeselect={false}
+                    value={format}
+                    onChange={(value) => value && setFormat(value as ExportFormat)}
+                />
+                <NumberInput
+                    label="Days"
+                    min={1}
+                    allowDecimal={false}
+                    value={days}
+                    onChange={(value) => typeof value === "number" && setDays(value)}
+                />
+                <Button onClick={exportHistory}>Export</Button>
+            </Group>
+            {message && <Text size="sm">{message}</Text>}
+        </div>
+    )
+}
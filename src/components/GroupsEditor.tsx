@@ -0,0 +1,103 @@
+import { ActionIcon, Button, Group, MultiSelect, NumberInput, Stack, Text, TextInput } from "@mantine/core";
+import { IconTrash } from "@tabler/icons-react";
+import { useQuery } from "@tanstack/react-query";
+import { useState } from "react";
+import { create_group, delete_group, errorMessage, get_groups, get_known_devices, send_group_command, set_group_temperature } from "../commands";
+import { ButtonCode, DeviceGroup, GroupResult, KnownDevice, TemperatureUnit, UserPreferences } from "../types";
+
+interface GroupItemProps {
+    group: DeviceGroup
+    devices: KnownDevice[]
+    config: UserPreferences
+    onChange: () => void
+}
+
+function GroupItem({ group, devices, config, onChange }: GroupItemProps) {
+    const [temp, setTemp] = useState(config.unit === TemperatureUnit.Celsius ? 30 : 86);
+    const [results, setResults] = useState<GroupResult[]>([]);
+    const [error, setError] = useState<string | null>(null);
+    const deviceName = (id: string) => devices.find((i) => i.id === id)?.name ?? id;
+
+    const run = (request: Promise<GroupResult[]>) => {
+        request
+            .then((results) => {
+                setError(null);
+                setResults(results);
+            })
+            .catch((err) => setError(errorMessage(err)));
+    };
+
+    return (
+        <Stack gap={4}>
+            <Group justify="space-between">
+                <div>
+                    <Text size="sm" fw={500}>{group.name}</Text>
+                    <Text size="xs">{group.members.map(deviceName).join(", ")}</Text>
+                </div>
+                <ActionIcon variant="outline" color="red" onClick={() => delete_group(group.id).then(onChange)}>
+                    <IconTrash />
+                </ActionIcon>
+            </Group>
+            <Group align="end">
+                <NumberInput label="Temperature" value={temp}
+                    onChange={(value) => typeof value === "number" && setTemp(value)} />
+                <Button onClick={() => run(set_group_temperature(
+                    group.id,
+                    config.unit === TemperatureUnit.Celsius ? { type: "Celsius", value: temp } : { type: "Fahrenheit", value: temp },
+                ))}>
+                    Set
+                </Button>
+                <Button variant="outline" onClick={() => run(send_group_command(group.id, { type: "Button", content: ButtonCode.Stop }))}>
+                    Off
+                </Button>
+            </Group>
+            {error && <Text size="xs" c="red">{error}</Text>}
+            {results.map((result) =>
+                <Text key={result.id} size="xs" c={result.error ? "red" : "dimmed"}>
+                    {deviceName(result.id)}: {result.error ? errorMessage(result.error) : "Done"}
+                </Text>
+            )}
+        </Stack>
+    )
+}
+
+export default function GroupsEditor({ config }: { config: UserPreferences }) {
+    const groups = useQuery({ queryKey: ["groups"], queryFn: () => get_groups() });
+    const devices = useQuery({ queryKey: ["known"], queryFn: () => get_known_devices() });
+
+    const [name, setName] = useState("");
+    const [members, setMembers] = useState<string[]>([]);
+    const [error, setError] = useState<string | null>(null);
+
+    const create = () => {
+        create_group(name, members)
+            .then(() => {
+                setError(null);
+                setName("");
+                setMembers([]);
+                groups.refetch();
+            })
+            .catch((err) => setError(errorMessage(err)));
+    };
+
+    return (
+        <Stack gap="xs" mt="md">
+            <Text size="sm" fw={500}>Groups</Text>
+            {groups.data?.map((group) =>
+                <GroupItem key={group.id} group={group} devices={devices.data ?? []} config={config}
+                    onChange={() => groups.refetch()} />
+            )}
+            <TextInput placeholder="Name" value={name} onChange={(event) => setName(event.currentTarget.value)} />
+            <MultiSelect
+                placeholder="Devices"
+                data={devices.data?.map((item) => ({ value: item.id, label: item.name ?? item.id })) ?? []}
+                value={members}
+                onChange={setMembers}
+            />
+            {error && <Text size="sm" c="red">{error}</Text>}
+            <Group>
+                <Button onClick={create} disabled={!name || members.length === 0}>Add Group</Button>
+            </Group>
+        </Stack>
+    )
+}
@@ -1,8 +1,7 @@
 import { SegmentedControl } from "@mantine/core";
 import { ButtonCode, OperatingMode, ParsedDeviceStatus } from "../types";
 import { useEffect, useState } from "react";
-import { setTemperature } from "../hooks/useSetTemp";
-import { send_command } from "../commands";
+import { send_command, set_target_temperature } from "../commands";
 
 
 interface ModeControlProps {
@@ -58,7 +57,7 @@ export function ModeControl({ bedjet, data }: ModeControlProps) {
                     })
                 }
                 if (mode === "Normal" && data) {
-                    setTemperature(bedjet, data, { type: "Celsius", value: data.target_temp })
+                    set_target_temperature(bedjet, { type: "Celsius", value: data.target_temp })
                 }
                 if (mode === "Heat") {
                     send_command(bedjet, { type: "Button", content: ButtonCode.Heat })
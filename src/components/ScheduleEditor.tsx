@@ -0,0 +1,118 @@
+import { ActionIcon, Button, Chip, Group, NumberInput, Select, Stack, Switch, Text, TextInput } from "@mantine/core";
+import { IconTrash } from "@tabler/icons-react";
+import { useQuery } from "@tanstack/react-query";
+import { useState } from "react";
+import { match } from "ts-pattern";
+import { create_schedule_rule, delete_schedule_rule, errorMessage, get_groups, get_known_devices, update_schedule_rule } from "../commands";
+import { useSchedule } from "../hooks";
+import { Day, RuleRun, ScheduleEntry, TemperatureUnit, UserPreferences } from "../types";
+
+const pad = (value: number) => String(value).padStart(2, "0");
+
+function describeRun(run?: RuleRun) {
+    if (!run) return "Hasn't run yet";
+    const time = new Date(run.scheduled * 1000).toLocaleString();
+    return match(run.outcome)
+        .with({ type: "Succeeded" }, () => `Ran for ${time}`)
+        .with({ type: "Failed" }, ({ value }) => `Failed for ${time}: ${value}`)
+        .with({ type: "Missed" }, () => `Missed ${time}`)
+        .exhaustive();
+}
+
+function RuleItem({ entry, onChange }: { entry: ScheduleEntry, onChange: () => void }) {
+    const { rule, last_run } = entry;
+    const { trigger } = rule.spec;
+    return (
+        <Group justify="space-between">
+            <div>
+                <Text size="sm" fw={500}>{rule.spec.name}</Text>
+                <Text size="xs">{trigger.days.join(", ")} at {pad(trigger.hour)}:{pad(trigger.minute)}</Text>
+                <Text size="xs" c="dimmed">{describeRun(last_run)}</Text>
+            </div>
+            <Group>
+                <Switch
+                    checked={rule.spec.enabled}
+                    onChange={(event) => {
+                        update_schedule_rule(rule.id, { ...rule.spec, enabled: event.currentTarget.checked }).then(onChange)
+                    }}
+                />
+                <ActionIcon variant="outline" color="red" onClick={() => delete_schedule_rule(rule.id).then(onChange)}>
+                    <IconTrash />
+                </ActionIcon>
+            </Group>
+        </Group>
+    )
+}
+
+export default function ScheduleEditor({ config }: { config: UserPreferences }) {
+    const schedule = useSchedule();
+    const devices = useQuery({ queryKey: ["known"], queryFn: () => get_known_devices() });
+    const groups = useQuery({ queryKey: ["groups"], queryFn: () => get_groups() });
+
+    const [name, setName] = useState("");
+    // Devices and groups share the select, so groups are prefixed to tell them apart
+    const [target, setTarget] = useState<string | null>(null);
+    const [days, setDays] = useState<string[]>([]);
+    const [hour, setHour] = useState(7);
+    const [minute, setMinute] = useState(0);
+    const [temp, setTemp] = useState(config.unit === TemperatureUnit.Celsius ? 30 : 86);
+    const [error, setError] = useState<string | null>(null);
+
+    const create = () => {
+        if (!target) return;
+        create_schedule_rule({
+            name,
+            enabled: true,
+            trigger: { days: days as Day[], hour, minute },
+            target: target.startsWith("group:")
+                ? { type: "Group", value: target.slice("group:".length) }
+                : { type: "Device", value: target },
+            action: {
+                type: "TargetTemperature",
+                value: config.unit === TemperatureUnit.Celsius ? { type: "Celsius", value: temp } : { type: "Fahrenheit", value: temp },
+            },
+        })
+            .then(() => {
+                setError(null);
+                setName("");
+                schedule.refetch();
+            })
+            .catch((err) => setError(errorMessage(err)));
+    };
+
+    return (
+        <Stack gap="xs" mt="md">
+            <Text size="sm" fw={500}>Schedule</Text>
+            {schedule.data?.map((entry) =>
+                <RuleItem key={entry.rule.id} entry={entry} onChange={() => schedule.refetch()} />
+            )}
+            <TextInput placeholder="Name" value={name} onChange={(event) => setName(event.currentTarget.value)} />
+            <Select
+                placeholder="Device or Group"
+                data={[
+                    { group: "Devices", items: devices.data?.map((item) => ({ value: item.id, label: item.name ?? item.id })) ?? [] },
+                    { group: "Groups", items: groups.data?.map((item) => ({ value: `group:${item.id}`, label: item.name })) ?? [] },
+                ]}
+                value={target}
+                onChange={setTarget}
+            />
+            <Chip.Group multiple value={days} onChange={setDays}>
+                <Group gap="xs">
+                    {Object.values(Day).map((day) => <Chip key={day} value={day} size="xs">{day.slice(0, 3)}</Chip>)}
+                </Group>
+            </Chip.Group>
+            <Group grow>
+                <NumberInput label="Hour" min={0} max={23} allowDecimal={false} value={hour}
+                    onChange={(value) => typeof value === "number" && setHour(value)} />
+                <NumberInput label="Minute" min={0} max={59} allowDecimal={false} value={minute}
+                    onChange={(value) => typeof value === "number" && setMinute(value)} />
+                <NumberInput label="Temperature" value={temp}
+                    onChange={(value) => typeof value === "number" && setTemp(value)} />
+            </Group>
+            {error && <Text size="sm" c="red">{error}</Text>}
+            <Group>
+                <Button onClick={create} disabled={!target || !name}>Add Rule</Button>
+            </Group>
+        </Stack>
+    )
+}
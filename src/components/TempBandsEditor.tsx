@@ -0,0 +1,92 @@
+import { Button, Group, NumberInput, Select, Stack, Text } from "@mantine/core";
+import { useEffect, useState } from "react";
+import { errorMessage, set_config } from "../commands";
+import { OperatingMode, TempBand, TemperatureUnit, UserPreferences } from "../types";
+import { CtoF, FtoC } from "../util";
+
+const BandModes = [
+    { value: OperatingMode.Cool, label: "Cool" },
+    { value: OperatingMode.Dry, label: "Dry" },
+    { value: OperatingMode.NormalHeat, label: "Heat" },
+    { value: OperatingMode.TurboHeat, label: "Turbo Heat" },
+    { value: OperatingMode.ExtendedHeat, label: "Extended Heat" },
+];
+
+const HeatModes = [OperatingMode.NormalHeat, OperatingMode.TurboHeat, OperatingMode.ExtendedHeat];
+
+/** Each mode can only be used once, and only one heat mode can be used, the same as the backend checks */
+function modesFor(bands: TempBand[], index: number) {
+    const others = bands.filter((_, i) => i !== index).map((band) => band.mode);
+    const heatTaken = others.some((mode) => HeatModes.includes(mode));
+    return BandModes.map((item) => ({
+        ...item,
+        disabled: others.includes(item.value) || (heatTaken && HeatModes.includes(item.value)),
+    }));
+}
+
+interface TempBandsEditorProps {
+    config: UserPreferences,
+    onSaved: () => void
+}
+
+export default function TempBandsEditor({ config, onSaved }: TempBandsEditorProps) {
+    const [bands, setBands] = useState<TempBand[]>(config.temp_bands?.bands ?? []);
+    const [error, setError] = useState<string | null>(null);
+
+    useEffect(() => setBands(config.temp_bands?.bands ?? []), [config]);
+
+    const celsius = config.unit === TemperatureUnit.Celsius;
+    const toUnit = (temp: number) => Number((celsius ? temp : CtoF(temp)).toFixed(1));
+    const fromUnit = (temp: number) => celsius ? temp : FtoC(temp);
+
+    // Bands are edited by where each one ends, so that they always meet the next one
+    const setMax = (index: number, value: number) => setBands(bands.map((band, i) => {
+        if (i === index) return { ...band, max: value };
+        if (i === index + 1) return { ...band, min: value };
+        return band;
+    }));
+    const setMin = (value: number) => setBands(bands.map((band, i) => i === 0 ? { ...band, min: value } : band));
+    const setMode = (index: number, mode: OperatingMode) => setBands(bands.map((band, i) => i === index ? { ...band, mode } : band));
+
+    const save = () => {
+        set_config({ ...config, temp_bands: { bands } })
+            .then(() => {
+                setError(null);
+                onSaved();
+            })
+            .catch((err) => setError(errorMessage(err)));
+    };
+
+    return (
+        <Stack gap="xs" mt="md">
+            <Text size="sm" fw={500}>Temperature Modes</Text>
+            {bands.length > 0 &&
+                <NumberInput
+                    label="From"
+                    value={toUnit(bands[0].min)}
+                    decimalScale={1}
+                    onChange={(value) => typeof value === "number" && setMin(fromUnit(value))}
+                />}
+            {bands.map((band, index) =>
+                <Group key={index} grow>
+                    <Select
+                        data={modesFor(bands, index)}
+                        allowDeselect={false}
+                        value={band.mode}
+                        onChange={(mode) => mode && setMode(index, mode as OperatingMode)}
+                    />
+                    <NumberInput
+                        placeholder="Up to"
+                        value={toUnit(band.max)}
+                        decimalScale={1}
+                        onChange={(value) => typeof value === "number" && setMax(index, fromUnit(value))}
+                    />
+                </Group>
+            )}
+            {error && <Text size="sm" c="red">{error}</Text>}
+            <Group>
+                <Button onClick={save}>Save</Button>
+            </Group>
+        </Stack>
+    )
+}
@@ -1,5 +1,5 @@
 import { MantineTheme, Slider, rem, useComputedColorScheme } from "@mantine/core";
-import { setTemperature } from "../hooks/useSetTemp";
+import { set_target_temperature } from "../commands";
 import { OperatingMode, ParsedDeviceStatus, TemperatureUnit, UserPreferences } from "../types";
 import { CtoF, FtoC } from "../util";
 import { useMantineTheme } from '@mantine/core';
@@ -51,12 +51,17 @@ export default function TempSlider({ bedjet, data }: TempSliderProps) {
         )
     }
 
-    const min = convertFixed(19, config)
-    const max = convertFixed(33.5, config)
+    // The backend only accepts bands that fit the device, but stay inside its limits until they've been fixed
+    const bands = config.temp_bands?.bands ?? []
+    const bandsMin = bands.length ? Math.min(...bands.map((band) => band.min)) : data.min_target_temp
+    const bandsMax = bands.length ? Math.max(...bands.map((band) => band.max)) : data.max_target_temp
+    const min = convertFixed(Math.max(data.min_target_temp, bandsMin), config)
+    const max = convertFixed(Math.min(data.max_target_temp, bandsMax), config)
     const selectedPercent = percent(value, min, max);
     return (
         <Slider
-            disabled={data.operating_mode === OperatingMode.TurboHeat || data.operating_mode === OperatingMode.NormalHeat}
+            disabled={(data.operating_mode === OperatingMode.TurboHeat || data.operating_mode === OperatingMode.NormalHeat)
+                && !bands.some((band) => band.mode === data.operating_mode)}
             min={min}
             max={max}
             step={config.unit === TemperatureUnit.Celsius ? 0.5 : 1}
@@ -78,7 +83,7 @@ export default function TempSlider({ bedjet, data }: TempSliderProps) {
             label={(label) => `${config.unit === TemperatureUnit.Celsius ? label : Math.round(label)}${getTempSymbol(config?.unit)}`}
             onChange={(val) => setValue(val)}
             onChangeEnd={(value) => {
-                setTemperature(bedjet, data, { type: "Celsius", value: config.unit === TemperatureUnit.Celsius ? value : FtoC(value) })
+                set_target_temperature(bedjet, { type: "Celsius", value: config.unit === TemperatureUnit.Celsius ? value : FtoC(value) })
             }}
             styles={{
                 root: {
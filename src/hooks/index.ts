@@ -2,6 +2,8 @@ import { useQuery, useQueryClient } from "@tanstack/react-query";
 import {
   get_adapters,
   get_config,
+  get_faults,
+  get_schedule,
   get_status,
   scan_devices,
 } from "../commands";
@@ -18,10 +20,17 @@ import {
   listen,
   UnlistenFn,
 } from "@tauri-apps/api/event";
-import { DeviceEvent, ParsedDeviceStatus, PeripheralResult } from "../types";
+import { DeviceEvent, DeviceFault, ParsedDeviceStatus, PeripheralResult, RuleRun } from "../types";
 import { usePrevious } from "@mantine/hooks";
 
 export function useAdapters() {
+  const queryClient = useQueryClient();
+  // Fires when an adapter is plugged in while there wasn't one to use
+  const handleAttached = useCallback(() => {
+    queryClient.invalidateQueries({ queryKey: ["adapters"] });
+  }, [queryClient]);
+  useSubscription<string>("AdapterAttached", handleAttached);
+
   return useQuery({
     queryKey: ["adapters"],
     queryFn: () => get_adapters(),
@@ -53,6 +62,33 @@ export function useDeviceStatus(
   });
 }
 
+export function useFaults(id: string | null | undefined) {
+  const queryClient = useQueryClient();
+  const handleFault = useCallback(() => {
+    queryClient.invalidateQueries({ queryKey: ["faults"] });
+  }, [queryClient]);
+  useSubscription<DeviceFault>("DeviceFault", handleFault);
+
+  return useQuery({
+    queryKey: ["faults", id],
+    queryFn: () => get_faults(id ?? undefined),
+    enabled: !!id,
+  });
+}
+
+export function useSchedule() {
+  const queryClient = useQueryClient();
+  const handleRun = useCallback(() => {
+    queryClient.invalidateQueries({ queryKey: ["schedule"] });
+  }, [queryClient]);
+  useSubscription<RuleRun>("ScheduleRun", handleRun);
+
+  return useQuery({
+    queryKey: ["schedule"],
+    queryFn: () => get_schedule(),
+  });
+}
+
 export function useSubscription<T>(
   id: string,
   onEvent: EventCallback<T>,
@@ -97,15 +133,28 @@ function useDeviceListSubscription() {
 
   const handleEvent = useCallback((event: Event<DeviceEvent>) => {
     console.log("DeviceEvent", event);
+    const payload = event.payload;
+    // Reconnect progress doesn't change the device list until the device is back or given up on
+    if (payload.type === "Reconnecting" || payload.type === "Reconnected") {
+      return;
+    }
     queryClient.setQueryData<PeripheralResult[]>(
       ["devices"],
       (cache) => {
+        const result: PeripheralResult =
+          payload.type === "GaveUp"
+            ? {
+                id: payload.value.id,
+                name: cache?.find((i) => i.id === payload.value.id)?.name,
+                connected: false,
+              }
+            : payload.value;
         if (cache === undefined) {
-          return [event.payload.value];
+          return [result];
         }
         const update = [...cache].map((i) =>
-          i.id === event.payload.value.id
-            ? { ...event.payload.value }
+          i.id === result.id
+            ? { ...result }
             : { ...i }
         );
 
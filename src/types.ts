@@ -2,9 +2,20 @@
  Generated by typeshare 1.6.0
 */
 
-export interface AdapterResult {
-	selected: string;
-	adapters: string[];
+export interface TempStats {
+	/** As degrees C */
+	min: number;
+	/** As degrees C */
+	max: number;
+	/** As degrees C, weighted by how long each temperature lasted */
+	mean: number;
+}
+
+export interface TargetDeviation {
+	/** How far the actual temperature was from the target on average, in degrees C */
+	mean: number;
+	/** The furthest the actual temperature got from the target, in degrees C */
+	max: number;
 }
 
 export enum OperatingMode {
@@ -17,6 +28,11 @@ export enum OperatingMode {
 	Wait = "Wait",
 }
 
+export interface ModeDuration {
+	mode: OperatingMode;
+	duration: number;
+}
+
 export enum ShutDownCode {
 	Normal = "Normal",
 	InvalidADC = "InvalidADC",
@@ -28,6 +44,73 @@ export enum ShutDownCode {
 	ExtenderThermalTrip = "ExtenderThermalTrip",
 }
 
+export interface ShutdownEvent {
+	/** As seconds since the unix epoch */
+	time: number;
+	code: ShutDownCode;
+}
+
+/** How the device behaved over one night */
+export interface NightSummary {
+	/** As seconds since the unix epoch */
+	start: number;
+	/** As seconds since the unix epoch */
+	end: number;
+	/** Each mode the device was in, and for how long. Time the device wasn't heard from isn't counted */
+	modes: ModeDuration[];
+	/** As a percent 0 - 100, while the device was running */
+	average_fan?: number;
+	actual_temp?: TempStats;
+	ambient_temp?: TempStats;
+	/** Only counts the time the device was running */
+	target_deviation?: TargetDeviation;
+	shutdowns: ShutdownEvent[];
+}
+
+export interface AdapterResult {
+	/** Missing when there's no adapter to use */
+	selected?: string;
+	adapters: string[];
+}
+
+/**
+ * The error every command returns to the UI.
+ * Each variant carries a message that can be shown to the user as is.
+ */
+export type CommandError = 
+	/** The device or adapter isn't around, or was never connected to */
+	| { type: "NotFound", value: string }
+	/** There's no Bluetooth adapter to talk to devices with */
+	| { type: "NoAdapter", value: string }
+	| { type: "MissingCharacteristic", value: string }
+	| { type: "Bluetooth", value: string }
+	/** The arguments were out of range, or the device sent back something that couldn't be understood */
+	| { type: "InvalidParameter", value: string }
+	| { type: "Timeout", value: string }
+	/** The command needs a valid confirmation before it's sent */
+	| { type: "Unconfirmed", value: string }
+	/** The device ran the request, but reported that it failed */
+	| { type: "DeviceFailed", value: string }
+	/** Saving to the app's database failed */
+	| { type: "Storage", value: string }
+	/** Reading or writing a file the user picked failed */
+	| { type: "File", value: string }
+	| { type: "Internal", value: string };
+
+/** How a group command went on one of the group's devices */
+export interface GroupResult {
+	id: string;
+	/** Missing when it succeeded */
+	error?: CommandError;
+}
+
+export interface TimeRange {
+	/** As seconds since the unix epoch */
+	from: number;
+	/** As seconds since the unix epoch */
+	to: number;
+}
+
 export enum UpdateStatus {
 	Idle = "Idle",
 	Starting = "Starting",
@@ -96,21 +179,271 @@ export interface DeviceStatusEvent {
 	status: DeviceStatus;
 }
 
+/**
+ * The device wide settings that can only be changed with buttons.
+ * 
+ * The device doesn't report them back, so they're only known after being applied.
+ */
+export interface DeviceSettings {
+	ring_light: boolean;
+	beeper_muted: boolean;
+	low_power_mode: boolean;
+	/** Whether the Wi-Fi/Bluetooth radio stays on */
+	radio_enabled: boolean;
+}
+
+/** The range of target temperatures that one mode covers, in degrees C */
+export interface TempBand {
+	mode: OperatingMode;
+	min: number;
+	max: number;
+}
+
+/** Picks the mode for a target temperature, so that one slider can go all the way from cooling to heating */
+export interface TempBands {
+	bands: TempBand[];
+}
+
+export interface VersionCode {
+	major: number;
+	minor: number;
+	patch: number;
+}
+
+/** The firmware versions reported by the FirmwareVersionCodes parameter */
+export interface FirmwareVersion {
+	/** The main controller that runs the heater and fan */
+	main: VersionCode;
+	/** The Wi-Fi/Bluetooth module */
+	radio: VersionCode;
+}
+
+/** A single step of a biorhythm program */
+export interface BiorhythmStep {
+	operating_mode: OperatingMode;
+	/** Stored in units of 0.5 degrees celsius */
+	target_temp: number;
+	/** Represented as a number between 0-19 */
+	fan_step: number;
+	/** How long the step runs for */
+	hours: number;
+	minutes: number;
+}
+
+/**
+ * A program of steps the device runs through one after another.
+ * 
+ * On the device it is stored as 64 bytes split across four 16 byte fragments.
+ * The first byte is the number of steps, followed by 5 bytes per step and zero padding.
+ */
+export interface Biorhythm {
+	/** Cannot contain more than [`Biorhythm::MAX_STEPS`] steps */
+	steps: BiorhythmStep[];
+}
+
+/** One of the three named memory presets stored on the device */
+export interface MemoryPreset {
+	/** Numbered 1-3 */
+	slot: number;
+	/** Cannot contain a String longer than 15 bytes. */
+	name: string;
+}
+
+/** One of the three named biorhythm programs stored on the device */
+export interface BiorhythmProgram {
+	/** Numbered 1-3 */
+	slot: number;
+	/** Cannot contain a String longer than 15 bytes. */
+	name: string;
+	biorhythm: Biorhythm;
+}
+
+export enum Day {
+	Monday = "Monday",
+	Tuesday = "Tuesday",
+	Wednesday = "Wednesday",
+	Thursday = "Thursday",
+	Friday = "Friday",
+	Saturday = "Saturday",
+	Sunday = "Sunday",
+}
+
+/** Fires at the same local time on each of the days */
+export interface Trigger {
+	days: Day[];
+	hour: number;
+	minute: number;
+}
+
+export type ScheduleTarget = 
+	| { type: "Device", value: string }
+	/** Every device in the group, by the group's id */
+	| { type: "Group", value: string };
+
+export type ScheduleAction = 
+	/** Sent in order */
+	| { type: "Commands", value: Command[] }
+	/** Switches modes to reach the temperature, the same way the temperature slider does */
+	| { type: "TargetTemperature", value: TempParam };
+
+/** Everything about a rule that the user picks */
+export interface RuleSpec {
+	name: string;
+	enabled: boolean;
+	trigger: Trigger;
+	target: ScheduleTarget;
+	action: ScheduleAction;
+}
+
+export interface ScheduleRule {
+	id: string;
+	/**
+	 * When the rule was created or last edited, as seconds since the unix epoch.
+	 * The rule never fires for times before this
+	 */
+	updated: number;
+	spec: RuleSpec;
+}
+
+export type RunOutcome = 
+	| { type: "Succeeded", value?: undefined }
+	| { type: "Failed", value: string }
+	/** The app wasn't open in time to run it */
+	| { type: "Missed", value?: undefined };
+
+export interface RuleRun {
+	rule: string;
+	/** When the rule was meant to fire, as seconds since the unix epoch */
+	scheduled: number;
+	/** When the rule actually ran, as seconds since the unix epoch */
+	ran_at: number;
+	outcome: RunOutcome;
+}
+
+/** A rule along with how it went the last time it fired */
+export interface ScheduleEntry {
+	rule: ScheduleRule;
+	last_run?: RuleRun;
+}
+
 export interface PeripheralResult {
 	id: string;
 	name?: string;
 	connected: boolean;
 }
 
+export interface DeviceInfo {
+	id: string;
+	name: string;
+	firmware: FirmwareVersion;
+}
+
+export type WifiProvisioningProgress = 
+	/** The SSID and password have been written to the device */
+	| { type: "CredentialsWritten", value?: undefined }
+	/** The device reported a new status while running the connection test */
+	| { type: "Testing", value: UpdateStatus }
+	| { type: "Succeeded", value?: undefined }
+	/** Contains the last error the device reported before the test failed */
+	| { type: "Failed", value: UpdateStatus }
+	| { type: "TimedOut", value?: undefined };
+
+export interface WifiProvisioningEvent {
+	id: string;
+	progress: WifiProvisioningProgress;
+}
+
+/** Devices that can be sent the same commands at once, like both sides of a bed */
+export interface DeviceGroup {
+	id: string;
+	name: string;
+	/** The ids of the devices in the group */
+	members: string[];
+}
+
+export interface KnownDevice {
+	id: string;
+	name?: string;
+	/** As seconds since the unix epoch */
+	last_connected: number;
+}
+
+/** The device started reporting a fault, after running normally */
+export interface DeviceFault {
+	id: string;
+	code: ShutDownCode;
+	/** As seconds since the unix epoch */
+	time: number;
+}
+
+/** A status the device reported, and when it reported it */
+export interface StatusSample {
+	/** As seconds since the unix epoch */
+	time: number;
+	status: ParsedDeviceStatus;
+}
+
+export type AutoconnectProgress = 
+	/** Waiting for the scan to find the device */
+	| { type: "Waiting", value?: undefined }
+	| { type: "Connecting", value?: undefined }
+	| { type: "Connected", value?: undefined }
+	/** Contains the reason the connection failed */
+	| { type: "Failed", value: string };
+
+export interface AutoconnectEvent {
+	id: string;
+	progress: AutoconnectProgress;
+}
+
+export type FirmwareUpdateProgress = 
+	/** The device is joining the network */
+	| { type: "Connecting", value?: undefined }
+	| { type: "Checking", value?: undefined }
+	| { type: "Updating", value?: undefined }
+	| { type: "Restarting", value?: undefined }
+	| { type: "Done", value?: undefined }
+	| { type: "UpToDate", value?: undefined }
+	| { type: "Failed", value: UpdateStatus }
+	| { type: "TimedOut", value?: undefined };
+
+export interface FirmwareUpdateEvent {
+	id: string;
+	progress: FirmwareUpdateProgress;
+}
+
+export interface ReconnectEvent {
+	id: string;
+	attempt: number;
+}
+
 export enum TemperatureUnit {
 	Fahrenheit = "Fahrenheit",
 	Celsius = "Celsius",
 }
 
+/** Which remembered devices to connect to on startup */
+export enum AutoconnectPreference {
+	All = "All",
+	/** Only the device that was connected to most recently */
+	LastUsed = "LastUsed",
+	None = "None",
+}
+
 export interface UserPreferences {
 	adapter: string;
 	unit: TemperatureUnit;
-	autoconnect_last_device: boolean;
+	autoconnect?: AutoconnectPreference;
+	/** Which mode to use for each range of target temperatures */
+	temp_bands?: TempBands;
+	/** How many days of status history to keep. Zero keeps all of it */
+	history_retention_days: number;
+}
+
+export enum ExportFormat {
+	Csv = "Csv",
+	/** One JSON object per line */
+	JsonLines = "JsonLines",
 }
 
 export enum ButtonCode {
@@ -153,6 +486,7 @@ export enum CommandClass {
 	SetFan = "SetFan",
 	SetClock = "SetClock",
 	SetParameter = "SetParameter",
+	GetParameter = "GetParameter",
 }
 
 export type TempParam = 
@@ -180,12 +514,50 @@ export type Command =
 }}
 	| { type: "SetParam", content: SetParamKind };
 
+/** A change to the device status that a command is expected to make */
+export type ExpectedStatus = 
+	| { type: "TargetTemp", value: TempParam }
+	| { type: "FanStep", value: FanParam }
+	| { type: "OperatingMode", value: OperatingMode }
+	/** The remaining time starts counting down straight away, so this matches for a minute after it's set */
+	| { type: "RemainingTime", value: {
+	hours: number;
+	minutes: number;
+}};
+
 export type SetParamKind = 
 	/** Cannot contain a String longer than 15 bytes. */
-	| { type: "DeviceName", value: string };
+	| { type: "DeviceName", value: string }
+	/** Cannot contain a String longer than 15 bytes. The slot is numbered 1-3 */
+	| { type: "MemoryName", value: {
+	slot: number;
+	name: string;
+}}
+	/** Cannot contain a String longer than 15 bytes. The slot is numbered 1-3 */
+	| { type: "BiorhythmName", value: {
+	slot: number;
+	name: string;
+}}
+	/** One 16 byte fragment of an encoded [`Biorhythm`]. The slot is numbered 1-3 and the fragment 1-4 */
+	| { type: "BiorhythmFragment", value: {
+	slot: number;
+	fragment: number;
+	data: number[];
+}};
+
+export enum FirmwareUpdateOutcome {
+	/** The update was installed and the device is restarting */
+	Updated = "Updated",
+	UpToDate = "UpToDate",
+}
 
 export type DeviceEvent = 
 	| { type: "Discovered", value: PeripheralResult }
 	| { type: "Disconnected", value: PeripheralResult }
-	| { type: "Connected", value: PeripheralResult };
+	| { type: "Connected", value: PeripheralResult }
+	/** Waiting to make another attempt at reconnecting to a dropped device */
+	| { type: "Reconnecting", value: ReconnectEvent }
+	| { type: "Reconnected", value: ReconnectEvent }
+	/** Every reconnect attempt failed, and the device has been forgotten */
+	| { type: "GaveUp", value: ReconnectEvent };
 